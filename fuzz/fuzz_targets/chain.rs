@@ -0,0 +1,16 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use rscompress_fuzz::check;
+use rscompress_transformation::{BurrowWheeler, Chain, MoveToFront, ZeroRunLength};
+
+fn bzip2() -> Chain {
+    Chain::with_stages(vec![
+        Box::new(BurrowWheeler::new()),
+        Box::new(MoveToFront::new()),
+        Box::new(ZeroRunLength::new()),
+    ])
+}
+
+fuzz_target!(|data: &[u8]| {
+    check(bzip2, data);
+});
@@ -0,0 +1,13 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use rscompress_fuzz::{check, split};
+use rscompress_transformation::Delta;
+
+fuzz_target!(|data: &[u8]| {
+    let (first, data) = split(data);
+    let (order, stride) = ((first & 0x03) as usize, (first >> 2 & 0x07) as usize + 1);
+    check(|| Delta::<u8>::with_order_and_stride(order, stride), data);
+    check(|| Delta::<i16>::with_order_and_stride(order, stride), data);
+    check(|| Delta::<u32>::with_order_and_stride(order, stride), data);
+    check(|| Delta::<u64>::with_order_and_stride(order, stride), data);
+});
@@ -0,0 +1,8 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use rscompress_coding::Huffman;
+use rscompress_fuzz::check_coding;
+
+fuzz_target!(|data: &[u8]| {
+    check_coding(Huffman::new, data);
+});
@@ -0,0 +1,8 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use rscompress_fuzz::check;
+use rscompress_transformation::Lz4Block;
+
+fuzz_target!(|data: &[u8]| {
+    check(Lz4Block::new, data);
+});
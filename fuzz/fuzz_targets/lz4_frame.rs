@@ -0,0 +1,20 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use rscompress_fuzz::{check, split};
+use rscompress_transformation::{Lz4BlockSize, Lz4Frame};
+
+fuzz_target!(|data: &[u8]| {
+    let (first, data) = split(data);
+    let block_size = match first & 0x03 {
+        0 => Lz4BlockSize::Max64KB,
+        1 => Lz4BlockSize::Max256KB,
+        2 => Lz4BlockSize::Max1MB,
+        _ => Lz4BlockSize::Max4MB,
+    };
+    let (block_checksum, content_checksum, content_size) =
+        (first & 0x04 != 0, first & 0x08 != 0, first & 0x10 != 0);
+    check(
+        || Lz4Frame::with_options(block_size, block_checksum, content_checksum, content_size),
+        data,
+    );
+});
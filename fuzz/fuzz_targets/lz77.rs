@@ -0,0 +1,13 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use rscompress_fuzz::{check, split};
+use rscompress_transformation::Lz77;
+
+fuzz_target!(|data: &[u8]| {
+    let (first, data) = split(data);
+    let window = 1 << (first & 0x0f);
+    check(
+        || Lz77::with_window_and_lazy(window, first & 0x10 != 0),
+        data,
+    );
+});
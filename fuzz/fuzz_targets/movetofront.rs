@@ -0,0 +1,23 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use rscompress_fuzz::{check, split};
+use rscompress_transformation::{Continuity, MoveToFront, Policy};
+
+fuzz_target!(|data: &[u8]| {
+    let (first, data) = split(data);
+    let policy = match first & 0x03 {
+        0 => Policy::Front,
+        1 => Policy::One,
+        2 => Policy::Two,
+        _ => Policy::Sticky,
+    };
+    let continuity = if first & 0x04 == 0 {
+        Continuity::Stream
+    } else {
+        Continuity::Block
+    };
+    check(
+        || MoveToFront::with_policy_and_continuity(policy, continuity),
+        data,
+    );
+});
@@ -0,0 +1,9 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use rscompress_fuzz::{check, split};
+use rscompress_transformation::PackBits;
+
+fuzz_target!(|data: &[u8]| {
+    let (first, data) = split(data);
+    check(|| PackBits::with_min_run((first & 0x07) as usize + 2), data);
+});
@@ -0,0 +1,27 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use rscompress_fuzz::{check, split};
+use rscompress_transformation::{PredictionResidual, Predictor, Shape};
+
+fuzz_target!(|data: &[u8]| {
+    let (first, data) = split(data);
+    let predictor = match first % 3 {
+        0 => Predictor::LastValue,
+        1 => Predictor::Plane,
+        _ => Predictor::Lorenzo,
+    };
+    let nx = (first >> 2 & 0x07) as usize + 1;
+    let shape = |size: usize| Shape::new(nx, data.len() / size / nx, 1);
+    check(
+        || PredictionResidual::<u8>::new(predictor, shape(1)),
+        &data[..shape(1).len()],
+    );
+    check(
+        || PredictionResidual::<i32>::new(predictor, shape(4)),
+        &data[..shape(4).len() * 4],
+    );
+    check(
+        || PredictionResidual::<f64>::new(predictor, shape(8)),
+        &data[..shape(8).len() * 8],
+    );
+});
@@ -0,0 +1,11 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use rscompress_fuzz::{check, split};
+use rscompress_transformation::{BitShuffle, ByteShuffle};
+
+fuzz_target!(|data: &[u8]| {
+    let (first, data) = split(data);
+    let element_size = (first & 0x0f) as usize + 1;
+    check(|| ByteShuffle::new(element_size), data);
+    check(|| BitShuffle::new(element_size), data);
+});
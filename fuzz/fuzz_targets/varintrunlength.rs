@@ -0,0 +1,8 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use rscompress_fuzz::check;
+use rscompress_transformation::VarintRunLength;
+
+fuzz_target!(|data: &[u8]| {
+    check(VarintRunLength::new, data);
+});
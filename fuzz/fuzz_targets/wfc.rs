@@ -0,0 +1,12 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use rscompress_fuzz::{check, split};
+use rscompress_transformation::WeightedFrequencyCount;
+
+fuzz_target!(|data: &[u8]| {
+    let (first, data) = split(data);
+    check(
+        || WeightedFrequencyCount::with_window(first as usize + 1),
+        data,
+    );
+});
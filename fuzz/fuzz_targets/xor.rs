@@ -0,0 +1,15 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use rscompress_fuzz::{check, split};
+use rscompress_transformation::{XorPredictor, XorResidual};
+
+fuzz_target!(|data: &[u8]| {
+    let (first, data) = split(data);
+    let predictor = match first % 3 {
+        0 => XorPredictor::Previous,
+        1 => XorPredictor::Linear,
+        _ => XorPredictor::Fcm,
+    };
+    check(|| XorResidual::<f32>::with_predictor(predictor), data);
+    check(|| XorResidual::<f64>::with_predictor(predictor), data);
+});
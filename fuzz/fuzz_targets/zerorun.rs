@@ -0,0 +1,8 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use rscompress_fuzz::check;
+use rscompress_transformation::ZeroRunLength;
+
+fuzz_target!(|data: &[u8]| {
+    check(ZeroRunLength::new, data);
+});
@@ -0,0 +1,42 @@
+//! Helper functions for the fuzz targets
+//!
+//! Each fuzz target checks that a transformation reverses its own output and
+//! that reversing arbitrary input returns an error instead of panicking.
+//! Codings are checked the same way with their `encode` and `decode`.
+use rscompress_coding::Coding;
+use rscompress_transformation::Transform;
+
+/// Check the roundtrip of the data and the reversal of the data itself
+pub fn check<M: Transform, F: Fn() -> M>(model: F, data: &[u8]) {
+    roundtrip(&model, data);
+    let _ = model().reverse(data);
+}
+
+/// Check the roundtrip of the data
+pub fn roundtrip<M: Transform, F: Fn() -> M>(model: F, data: &[u8]) {
+    let mut model = model();
+    let tmp = model.transform(data).unwrap();
+    assert_eq!(model.reverse(&tmp).unwrap(), data);
+}
+
+/// Check the roundtrip of the data as symbols and the decoding of the data itself
+///
+/// The symbols are the data read as `u32` in little-endian order.
+pub fn check_coding<C: Coding, F: Fn() -> C>(model: F, data: &[u8]) {
+    let symbols: Vec<u32> = data
+        .chunks_exact(4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect();
+    let mut coder = model();
+    let encoded = coder.encode(&symbols).unwrap();
+    assert_eq!(coder.decode(&encoded).unwrap(), symbols);
+    let _ = model().decode(data);
+}
+
+/// Split the first byte of the data for the selection of model parameters
+pub fn split(data: &[u8]) -> (u8, &[u8]) {
+    match data.split_first() {
+        Some((first, rest)) => (*first, rest),
+        None => (0, data),
+    }
+}
@@ -0,0 +1,152 @@
+//! Bitwise Real Information Content
+//!
+//! Analysis of the information stored in each bit of floating point numbers following
+//! [Klöwer et al. (2021)](https://doi.org/10.1038/s43588-021-00156-2).
+//! The information of a bit is the mutual information between the bit of adjacent elements.
+//! Bits which are not correlated with their neighbours, like the trailing bits of noisy
+//! data, carry no real information and can be discarded.
+use crate::Float;
+use log::debug;
+
+/// Quantile of the standard normal distribution for the confidence of 99%
+const CONFIDENCE_Z: f64 = 2.575_829_303_548_901;
+
+/// Entropy in bits of the probabilities
+fn entropy(probabilities: &[f64]) -> f64 {
+    probabilities
+        .iter()
+        .filter(|p| **p > 0.0)
+        .map(|p| -p * p.log2())
+        .sum()
+}
+
+/// Bitwise real information content of the data
+///
+/// The result contains the information of each bit, starting at the sign bit, followed
+/// by the exponent and the mantissa bits. Information which can not be distinguished
+/// from the information of random bits with a confidence of 99% is set to zero.
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress_approximation::bitinformation;
+/// let mut data = vec![1.0f32; 1000];
+/// data.extend_from_slice(&[1.5f32; 1000]);
+/// let information = bitinformation(&data);
+/// assert_eq!(information.len(), 32);
+/// assert!(information[9] > 0.99);
+/// assert_eq!(information.iter().filter(|i| **i > 0.0).count(), 1);
+/// ```
+pub fn bitinformation<T: Float>(data: &[T]) -> Vec<f64> {
+    let bits = T::BITS as usize;
+    // counts[bit][previous][next]
+    let mut counts = vec![[[0usize; 2]; 2]; bits];
+    for pair in data.windows(2) {
+        let (a, b) = (pair[0].to_bits64(), pair[1].to_bits64());
+        for (i, count) in counts.iter_mut().enumerate() {
+            let shift = bits - 1 - i;
+            count[(a >> shift & 1) as usize][(b >> shift & 1) as usize] += 1;
+        }
+    }
+    let pairs = data.len().saturating_sub(1);
+    if pairs == 0 {
+        return vec![0.0; bits];
+    }
+    // entropy of a random bit, which appears to be biased by chance
+    let p = 0.5 + CONFIDENCE_Z / (2.0 * (pairs as f64).sqrt());
+    let insignificant = 1.0 - entropy(&[p.min(1.0), 1.0 - p.min(1.0)]);
+    counts
+        .iter()
+        .map(|count| {
+            let joint: Vec<f64> = count
+                .iter()
+                .flat_map(|row| row.iter().map(|c| *c as f64 / pairs as f64))
+                .collect();
+            let previous = [joint[0] + joint[1], joint[2] + joint[3]];
+            let next = [joint[0] + joint[2], joint[1] + joint[3]];
+            let information = entropy(&previous) + entropy(&next) - entropy(&joint);
+            if information > insignificant {
+                information
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+/// Minimal number of mantissa bits preserving the given share of the real information
+///
+/// The bits are kept from the sign bit on until the cumulative information reaches
+/// `level` (e.g. `0.99`) of the total information.
+/// Data without any real information, e.g. constant data or data without
+/// correlation between adjacent elements, keeps all mantissa bits.
+pub fn keepbits<T: Float>(data: &[T], level: f64) -> u32 {
+    let information = bitinformation(data);
+    let total: f64 = information.iter().sum();
+    if total <= 0.0 {
+        return T::MANTISSA_BITS;
+    }
+    let mut cumulative = 0.0;
+    let mut kept = information.len();
+    for (i, bit) in information.iter().enumerate() {
+        cumulative += bit;
+        if cumulative >= level * total {
+            kept = i + 1;
+            break;
+        }
+    }
+    let leading = (T::BITS - T::MANTISSA_BITS) as usize;
+    let result = kept.saturating_sub(leading).min(T::MANTISSA_BITS as usize) as u32;
+    debug!(
+        "Keeping {} mantissa bits for {:.4} of {:.4} bits of information",
+        result,
+        level * total,
+        total
+    );
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{noisy_signal, normal_f32};
+
+    #[test]
+    fn test_runs() {
+        let mut data = vec![1.0f64; 5_000];
+        data.extend_from_slice(&[1.5; 5_000]);
+        data.extend_from_slice(&[1.25; 5_000]);
+        let information = bitinformation(&data);
+        assert_eq!(information.len(), 64);
+        assert!(information[12] > 0.9 && information[13] > 0.9);
+        assert!(information[14..].iter().all(|i| *i == 0.0));
+        assert_eq!(keepbits(&data, 0.4), 1);
+        assert_eq!(keepbits(&data, 0.99), 2);
+    }
+
+    #[test]
+    fn test_without_information() {
+        assert!(bitinformation::<f32>(&[]).iter().all(|i| *i == 0.0));
+        assert_eq!(keepbits::<f32>(&[1.3], 0.99), 23);
+        assert_eq!(keepbits(&[1.3f64; 1_000], 0.99), 52);
+        // independent samples have no information between neighbours
+        assert_eq!(keepbits(&normal_f32()[..100_000], 0.99), 23);
+    }
+
+    #[test]
+    fn test_noise_is_discarded() {
+        let exact = keepbits(&noisy_signal(0.0), 0.99);
+        let fine = keepbits(&noisy_signal(1e-6), 0.99);
+        let coarse = keepbits(&noisy_signal(1e-2), 0.99);
+        assert!(
+            coarse < fine && fine <= exact,
+            "{} {} {}",
+            coarse,
+            fine,
+            exact
+        );
+        // noise of 1e-2 on values between 50 and 150 leaves about 13 significant bits
+        assert!((8..=16).contains(&coarse), "{}", coarse);
+        assert!(keepbits(&noisy_signal(1e-2), 0.5) < coarse);
+    }
+}
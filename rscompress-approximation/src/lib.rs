@@ -1,7 +1,210 @@
+//! This crate implements approximation algorithms to be used during lossy compression.
+//!
+//! # Introduction
+//! The approximation algorithms implemented in this crate are not reversible.
+//! They map the data onto integer bins, which can be compressed by a coder, and
+//! guarantee that the data restored from the bins is within the threshold `theta`
+//! defined by each algorithm.
+use std::fmt;
+use std::{error::Error, fmt::Display};
+
+mod information;
+mod linear;
+mod mask;
+mod relative;
+mod report;
+mod truncation;
+
+pub use information::{bitinformation, keepbits};
+pub use linear::{ErrorBound, LinearQuantizer};
+pub use mask::{MaskedApproximation, MASKED};
+pub use relative::{RelativeQuantizer, ZERO};
+pub use report::ErrorReport;
+pub use rscompress_types::Float;
+pub use truncation::{BitTruncation, Precision, Truncation};
+
+/// Bin marking an element which is stored unchanged as outlier
+pub const OUTLIER: i64 = i64::MIN;
+
+/// Data approximated by integer bins
+///
+/// Elements which can not be approximated within the threshold (e.g. infinities
+/// and NaN) are marked by the [`OUTLIER`] bin and stored unchanged in `outliers`
+/// in the order of their appearance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantized<T> {
+    pub bins: Vec<i64>,
+    pub outliers: Vec<T>,
+}
+
+impl<T> Quantized<T> {
+    /// Number of approximated elements
+    pub fn len(&self) -> usize {
+        self.bins.len()
+    }
+    /// Check if no element is approximated
+    pub fn is_empty(&self) -> bool {
+        self.bins.is_empty()
+    }
+}
+
+/// Trait for approximating floating point numbers within a guaranteed threshold
+pub trait Approximate<T: Float> {
+    fn approximate(&mut self, data: &[T]) -> Result<Quantized<T>, ApproximationError>;
+    fn restore(&mut self, quantized: &Quantized<T>) -> Result<Vec<T>, ApproximationError>;
+}
+
+/// Join bins and outliers into the restored data
+///
+/// The closure restores the value of each bin, which is not marked as outlier.
+pub(crate) fn restore_with<T: Float, F: Fn(i64) -> T>(
+    quantized: &Quantized<T>,
+    restore: F,
+) -> Result<Vec<T>, ApproximationError> {
+    let markers = quantized.bins.iter().filter(|b| **b == OUTLIER).count();
+    if markers != quantized.outliers.len() {
+        return Err(ApproximationError::OutlierMismatch(
+            markers,
+            quantized.outliers.len(),
+        ));
+    }
+    let mut outliers = quantized.outliers.iter();
+    let result = quantized
+        .bins
+        .iter()
+        .map(|b| match *b {
+            OUTLIER => *outliers.next().unwrap(),
+            b => restore(b),
+        })
+        .collect();
+    Ok(result)
+}
+
+/// An enum representing possible errors during approximation
+#[derive(Debug)]
+pub enum ApproximationError {
+    /// Threshold is not a positive finite number
+    InvalidThreshold(f64),
+    /// Number of outlier bins does not match the outliers (expected, actual)
+    OutlierMismatch(usize, usize),
+    /// Number of elements of the original and the restored data differ (expected, actual)
+    LengthMismatch(usize, usize),
+    /// Region is not assigned to an approximation
+    InvalidRegion(usize),
+}
+
+impl Error for ApproximationError {
+    fn description(&self) -> &str {
+        match *self {
+            ApproximationError::InvalidThreshold(_) => "Invalid threshold",
+            ApproximationError::OutlierMismatch(_, _) => "Outlier mismatch",
+            ApproximationError::LengthMismatch(_, _) => "Length mismatch",
+            ApproximationError::InvalidRegion(_) => "Invalid region",
+        }
+    }
+}
+
+impl Display for ApproximationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ApproximationError::InvalidThreshold(val) => {
+                write!(f, "Threshold [{:?}] must be positive and finite", val)
+            }
+            ApproximationError::OutlierMismatch(expected, actual) => {
+                write!(f, "Expected {:?} outliers but got {:?}", expected, actual)
+            }
+            ApproximationError::LengthMismatch(expected, actual) => {
+                write!(f, "Expected {:?} elements but got {:?}", expected, actual)
+            }
+            ApproximationError::InvalidRegion(region) => {
+                write!(f, "Region {:?} has no approximation", region)
+            }
+        }
+    }
+}
+
 #[cfg(test)]
-mod tests {
-    #[test]
-    fn it_works() {
-        assert_eq!(2 + 2, 4);
+#[allow(dead_code)]
+pub mod tests {
+    //! # Tests
+    //! This module defines helper functions for testing approximation algorithms.
+    use crate::{Approximate, Float};
+    pub use rscompress_testing::config;
+    use std::fs;
+    use std::sync::OnceLock;
+
+    /// Values of the testdata with normal distribution (mean 256, std 25.6)
+    pub fn normal_f32() -> &'static [f32] {
+        static DATA: OnceLock<Vec<f32>> = OnceLock::new();
+        DATA.get_or_init(|| {
+            let path = concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/../testdata/f32_256normal7.raw"
+            );
+            let bytes = fs::read(path).unwrap();
+            bytes
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect()
+        })
+    }
+
+    /// Smooth signal with uniform noise of the given amplitude
+    pub fn noisy_signal(noise: f64) -> Vec<f64> {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        (0..100_000)
+            .map(|k| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                let uniform = (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5;
+                100.0 + 50.0 * (k as f64 * 1e-3).sin() + noise * uniform
+            })
+            .collect()
+    }
+
+    /// Helper function for approximating and restoring data
+    ///
+    /// The number of bins must match the data and non-finite values must be restored exactly.
+    pub fn approximation_with<T: Float, M: Approximate<T>>(model: &mut M, data: &[T]) -> Vec<T> {
+        let quantized = model.approximate(data).unwrap();
+        assert_eq!(quantized.len(), data.len());
+        let result = model.restore(&quantized).unwrap();
+        assert_eq!(result.len(), data.len());
+        for (x, y) in data.iter().zip(result.iter()) {
+            if !x.to_f64().is_finite() {
+                assert_eq!(x.to_bits64(), y.to_bits64());
+            }
+        }
+        result
+    }
+
+    /// Helper function for testing the absolute error bound of an approximation
+    pub fn absolute_bound_with<T: Float, M: Approximate<T>>(model: &mut M, data: &[T], theta: f64) {
+        let result = approximation_with(model, data);
+        for (x, y) in data.iter().zip(result.iter()) {
+            let (x, y) = (x.to_f64(), y.to_f64());
+            if x.is_finite() {
+                assert!((x - y).abs() <= theta, "|{} - {}| > {}", x, y, theta);
+            }
+        }
+    }
+
+    /// Helper function for testing the point-wise relative error bound of an approximation
+    pub fn relative_bound_with<T: Float, M: Approximate<T>>(model: &mut M, data: &[T], theta: f64) {
+        let result = approximation_with(model, data);
+        for (x, y) in data.iter().zip(result.iter()) {
+            let (x, y) = (x.to_f64(), y.to_f64());
+            if x.is_finite() {
+                assert!(
+                    (x - y).abs() <= theta * x.abs(),
+                    "|{} - {}| > {} * |{}|",
+                    x,
+                    y,
+                    theta,
+                    x
+                );
+            }
+        }
     }
 }
@@ -0,0 +1,238 @@
+//! Linear Quantization
+//!
+//! Quantization of floating point numbers onto equidistant bins guaranteeing
+//! an absolute error bound `|x - x'| <= theta`.
+use crate::{restore_with, Approximate, ApproximationError, Float, Quantized, OUTLIER};
+use log::debug;
+
+/// Largest bin whose value is represented exactly by a `f64`
+const MAX_BIN: f64 = (1u64 << 53) as f64;
+
+/// Linear quantizer struct with the absolute error bound `theta`
+///
+/// The bins have a width of `2 * theta` and are centered around multiples of it,
+/// such that zero is always restored exactly.
+/// The restored value is checked against the bound after rounding to the
+/// precision of the element type. Elements violating the bound, e.g. because the
+/// precision of `f32` is coarser than `theta`, as well as infinities and NaN are
+/// stored as outliers.
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress_approximation::{Approximate, LinearQuantizer};
+/// let mut model = LinearQuantizer::new(0.5);
+/// let quantized = model.approximate(&[0.2f32, 1.7, -3.1]).unwrap();
+/// assert_eq!(quantized.bins, [0, 2, -3]);
+/// assert_eq!(model.restore(&quantized).unwrap(), [0.0, 2.0, -3.0]);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct LinearQuantizer {
+    theta: f64,
+}
+
+impl LinearQuantizer {
+    pub fn new(theta: f64) -> Self {
+        LinearQuantizer { theta }
+    }
+    /// Absolute error bound of the quantizer
+    pub fn theta(&self) -> f64 {
+        self.theta
+    }
+    /// Bin of a single value, or `None` if the value is outside of the bins
+    ///
+    /// The bound is not checked after rounding to the precision of the element type.
+    pub fn quantize(&self, value: f64) -> Option<i64> {
+        let bin = (value / (2.0 * self.theta)).round();
+        if bin.abs() < MAX_BIN {
+            Some(bin as i64)
+        } else {
+            None
+        }
+    }
+    /// Value of a single bin
+    pub fn dequantize(&self, bin: i64) -> f64 {
+        bin as f64 * (2.0 * self.theta)
+    }
+    /// Check if the threshold is a positive finite number
+    pub fn validate(&self) -> Result<(), ApproximationError> {
+        if !(self.theta > 0.0 && (2.0 * self.theta).is_finite()) {
+            return Err(ApproximationError::InvalidThreshold(self.theta));
+        }
+        Ok(())
+    }
+}
+
+/// Error bounds of approximations
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorBound {
+    /// Absolute error bound
+    Absolute(f64),
+    /// Error bound relative to the value range of the finite values of the data
+    Relative(f64),
+}
+
+impl ErrorBound {
+    /// Absolute error bound for the data
+    ///
+    /// Data without a value range, e.g. constant data, results in an error bound
+    /// of the smallest positive normal `f64`.
+    pub fn absolute<T: Float>(&self, data: &[T]) -> f64 {
+        match *self {
+            ErrorBound::Absolute(theta) => theta,
+            ErrorBound::Relative(ratio) => {
+                let (min, max) = data
+                    .iter()
+                    .map(|x| x.to_f64())
+                    .filter(|x| x.is_finite())
+                    .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), x| {
+                        (min.min(x), max.max(x))
+                    });
+                let range = max - min;
+                if range > 0.0 {
+                    ratio * range
+                } else {
+                    f64::MIN_POSITIVE
+                }
+            }
+        }
+    }
+}
+
+/// Implementation of the Approximate trait for Linear Quantization
+impl<T: Float> Approximate<T> for LinearQuantizer {
+    fn approximate(&mut self, data: &[T]) -> Result<Quantized<T>, ApproximationError> {
+        self.validate()?;
+        let mut quantized = Quantized {
+            bins: Vec::with_capacity(data.len()),
+            outliers: Vec::new(),
+        };
+        for x in data.iter() {
+            let value = x.to_f64();
+            match self.quantize(value) {
+                Some(bin)
+                    if (value - T::from_f64(self.dequantize(bin)).to_f64()).abs() <= self.theta =>
+                {
+                    quantized.bins.push(bin)
+                }
+                _ => {
+                    quantized.bins.push(OUTLIER);
+                    quantized.outliers.push(*x);
+                }
+            }
+        }
+        debug!(
+            "Quantized {} elements with {} outliers",
+            data.len(),
+            quantized.outliers.len()
+        );
+        Ok(quantized)
+    }
+
+    fn restore(&mut self, quantized: &Quantized<T>) -> Result<Vec<T>, ApproximationError> {
+        self.validate()?;
+        restore_with(quantized, |bin| T::from_f64(self.dequantize(bin)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{absolute_bound_with, approximation_with, config, normal_f32};
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_bins() {
+        let mut model = LinearQuantizer::new(0.05);
+        let quantized = model
+            .approximate(&[0.0f64, 0.04, 0.06, -0.24, 1.0])
+            .unwrap();
+        assert_eq!(quantized.bins, [0, 0, 1, -2, 10]);
+        assert!(quantized.outliers.is_empty());
+        let restored = model.restore(&quantized).unwrap();
+        assert_eq!(restored, [0.0, 0.0, 0.1, -0.2, 1.0]);
+    }
+
+    #[test]
+    fn test_error_bound() {
+        let data = [1.0f32, f32::NAN, -3.0, 5.0, f32::INFINITY];
+        assert_eq!(ErrorBound::Absolute(0.1).absolute(&data), 0.1);
+        assert_eq!(ErrorBound::Relative(0.01).absolute(&data), 0.08);
+        assert_eq!(
+            ErrorBound::Relative(0.01).absolute(&[2.0f64; 3]),
+            f64::MIN_POSITIVE
+        );
+        let model = LinearQuantizer::new(0.25);
+        assert_eq!(model.quantize(1.3), Some(3));
+        assert_eq!(model.dequantize(-3), -1.5);
+        assert_eq!(model.quantize(f64::NAN), None);
+        assert_eq!(model.quantize(1e300), None);
+    }
+
+    #[test]
+    fn test_outliers() {
+        let mut model = LinearQuantizer::new(1e-3);
+        let data = [1.0f32, f32::NAN, f32::INFINITY, 1e30, -f32::INFINITY, 2.0];
+        let quantized = model.approximate(&data).unwrap();
+        assert_eq!(quantized.bins[0], 500);
+        assert_eq!(quantized.bins[5], 1000);
+        assert_eq!(quantized.outliers.len(), 4);
+        let restored = approximation_with(&mut model, &data);
+        assert_eq!(restored[3], 1e30);
+    }
+
+    #[test]
+    fn test_invalid_threshold() {
+        for theta in [0.0, -1.0, f64::NAN, f64::INFINITY, f64::MAX].iter() {
+            let mut model = LinearQuantizer::new(*theta);
+            assert!(matches!(
+                model.approximate(&[1.0f32]),
+                Err(ApproximationError::InvalidThreshold(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_outlier_mismatch() {
+        let mut model = LinearQuantizer::new(0.5);
+        let quantized = Quantized {
+            bins: vec![1, OUTLIER, OUTLIER],
+            outliers: vec![1.5f64],
+        };
+        assert!(matches!(
+            model.restore(&quantized),
+            Err(ApproximationError::OutlierMismatch(2, 1))
+        ));
+    }
+
+    #[test]
+    fn test_normal_testdata() {
+        let data = normal_f32();
+        for theta in [1e-4, 1e-2, 0.5, 25.6].iter() {
+            let mut model = LinearQuantizer::new(*theta);
+            absolute_bound_with(&mut model, &data[..100_000], *theta);
+        }
+    }
+
+    proptest! {
+        #![proptest_config(config())]
+
+        #[test]
+        fn test_bound_on_testdata(start in 0usize..2_400_000, len in 0usize..4_096, theta in 1e-6f64..100.0) {
+            let data = normal_f32();
+            let end = (start + len).min(data.len());
+            let mut model = LinearQuantizer::new(theta);
+            absolute_bound_with(&mut model, &data[start..end], theta);
+        }
+
+        #[test]
+        fn test_bound_f32(data in prop::collection::vec(any::<f32>(), 0..256), theta in 1e-9f64..1e9) {
+            absolute_bound_with(&mut LinearQuantizer::new(theta), &data, theta);
+        }
+
+        #[test]
+        fn test_bound_f64(data in prop::collection::vec(any::<f64>(), 0..256), theta in 1e-300f64..1e300) {
+            absolute_bound_with(&mut LinearQuantizer::new(theta), &data, theta);
+        }
+    }
+}
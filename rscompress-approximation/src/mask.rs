@@ -0,0 +1,313 @@
+//! Masked Approximation
+//!
+//! Approximation of data with masked elements, e.g. land points of ocean fields or
+//! missing values, and regions of interest with individual error bounds.
+//! Masked elements are marked by the constant [`MASKED`] bin and restored as fill value,
+//! such that they cost almost nothing after coding.
+use crate::{Approximate, ApproximationError, Float, Quantized, OUTLIER};
+use log::debug;
+
+/// Bin marking a masked element, which is restored as fill value
+pub const MASKED: i64 = i64::MIN + 1;
+
+/// Masked approximation struct with the fill value, the regions and their approximations
+///
+/// Elements whose bits equal the fill value are always masked, which includes a NaN fill value.
+/// Additionally, each element can be assigned to a region or be masked explicitly.
+/// Each region is approximated with its own model, e.g. a [`LinearQuantizer`](crate::LinearQuantizer)
+/// with a tighter bound for the region of interest.
+/// Bins of the regions which equal [`MASKED`] are stored as outliers.
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress_approximation::{Approximate, LinearQuantizer, MaskedApproximation, MASKED};
+/// let data = [1.02f32, -999.0, 2.51, -999.0];
+/// let mut model = MaskedApproximation::new(-999.0, LinearQuantizer::new(0.05));
+/// let quantized = model.approximate(&data).unwrap();
+/// assert_eq!(quantized.bins, [10, MASKED, 25, MASKED]);
+/// assert_eq!(model.restore(&quantized).unwrap(), [1.0, -999.0, 2.5, -999.0]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct MaskedApproximation<T, A> {
+    fill: T,
+    regions: Option<Vec<Option<usize>>>,
+    models: Vec<A>,
+}
+
+impl<T: Float, A: Approximate<T>> MaskedApproximation<T, A> {
+    /// Mask the elements equal to the fill value
+    pub fn new(fill: T, model: A) -> Self {
+        MaskedApproximation {
+            fill,
+            regions: None,
+            models: vec![model],
+        }
+    }
+    /// Mask the elements which are not valid and the elements equal to the fill value
+    pub fn with_mask(fill: T, valid: &[bool], model: A) -> Self {
+        let regions = valid
+            .iter()
+            .map(|v| if *v { Some(0) } else { None })
+            .collect();
+        MaskedApproximation {
+            fill,
+            regions: Some(regions),
+            models: vec![model],
+        }
+    }
+    /// Approximate the elements of each region with the model of the same index
+    ///
+    /// Elements without region and elements equal to the fill value are masked.
+    pub fn with_regions(fill: T, regions: Vec<Option<usize>>, models: Vec<A>) -> Self {
+        MaskedApproximation {
+            fill,
+            regions: Some(regions),
+            models,
+        }
+    }
+    /// Region of each element, `None` for masked elements
+    fn regions(&self, data: &[T]) -> Result<Vec<Option<usize>>, ApproximationError> {
+        let fill = self.fill.to_bits64();
+        let mut result = match &self.regions {
+            Some(regions) if regions.len() != data.len() => {
+                return Err(ApproximationError::LengthMismatch(
+                    regions.len(),
+                    data.len(),
+                ))
+            }
+            Some(regions) => regions.clone(),
+            None => vec![Some(0); data.len()],
+        };
+        for (region, x) in result.iter_mut().zip(data.iter()) {
+            match *region {
+                Some(r) if r >= self.models.len() => {
+                    return Err(ApproximationError::InvalidRegion(r))
+                }
+                Some(_) if x.to_bits64() == fill => *region = None,
+                _ => {}
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Implementation of the Approximate trait for Masked Approximation
+impl<T: Float, A: Approximate<T>> Approximate<T> for MaskedApproximation<T, A> {
+    fn approximate(&mut self, data: &[T]) -> Result<Quantized<T>, ApproximationError> {
+        let regions = self.regions(data)?;
+        let mut bins = vec![MASKED; data.len()];
+        let mut outliers: Vec<(usize, T)> = Vec::new();
+        for (r, model) in self.models.iter_mut().enumerate() {
+            let positions: Vec<usize> =
+                (0..data.len()).filter(|i| regions[*i] == Some(r)).collect();
+            let values: Vec<T> = positions.iter().map(|i| data[*i]).collect();
+            let quantized = model.approximate(&values)?;
+            let mut region_outliers = quantized.outliers.iter();
+            for (i, bin) in positions.iter().zip(quantized.bins.iter()) {
+                match *bin {
+                    OUTLIER => {
+                        let outlier =
+                            region_outliers
+                                .next()
+                                .ok_or(ApproximationError::OutlierMismatch(
+                                    quantized.outliers.len() + 1,
+                                    quantized.outliers.len(),
+                                ))?;
+                        outliers.push((*i, *outlier));
+                    }
+                    // the original value is restored exactly by the outlier
+                    MASKED => outliers.push((*i, data[*i])),
+                    bin => {
+                        bins[*i] = bin;
+                        continue;
+                    }
+                }
+                bins[*i] = OUTLIER;
+            }
+        }
+        outliers.sort_unstable_by_key(|(i, _)| *i);
+        let masked = bins.iter().filter(|b| **b == MASKED).count();
+        debug!(
+            "Approximated {} elements with {} masked elements in {} regions",
+            data.len(),
+            masked,
+            self.models.len()
+        );
+        Ok(Quantized {
+            bins,
+            outliers: outliers.into_iter().map(|(_, x)| x).collect(),
+        })
+    }
+
+    fn restore(&mut self, quantized: &Quantized<T>) -> Result<Vec<T>, ApproximationError> {
+        if let Some(regions) = &self.regions {
+            if regions.len() != quantized.len() {
+                return Err(ApproximationError::LengthMismatch(
+                    regions.len(),
+                    quantized.len(),
+                ));
+            }
+        }
+        let markers = quantized.bins.iter().filter(|b| **b == OUTLIER).count();
+        if markers != quantized.outliers.len() {
+            return Err(ApproximationError::OutlierMismatch(
+                markers,
+                quantized.outliers.len(),
+            ));
+        }
+        // split the bins and outliers into the regions
+        let mut parts: Vec<(Vec<usize>, Quantized<T>)> = (0..self.models.len())
+            .map(|_| {
+                (
+                    Vec::new(),
+                    Quantized {
+                        bins: Vec::new(),
+                        outliers: Vec::new(),
+                    },
+                )
+            })
+            .collect();
+        let mut outliers = quantized.outliers.iter();
+        for (i, bin) in quantized.bins.iter().enumerate() {
+            if *bin == MASKED {
+                continue;
+            }
+            let outlier = match *bin {
+                OUTLIER => outliers.next(),
+                _ => None,
+            };
+            // elements without region are restored as fill value
+            let r = match &self.regions {
+                Some(regions) => match regions[i] {
+                    Some(r) => r,
+                    None => continue,
+                },
+                None => 0,
+            };
+            let (positions, part) = parts
+                .get_mut(r)
+                .ok_or(ApproximationError::InvalidRegion(r))?;
+            positions.push(i);
+            part.bins.push(*bin);
+            part.outliers.extend(outlier);
+        }
+        let mut result = vec![self.fill; quantized.len()];
+        for (model, (positions, part)) in self.models.iter_mut().zip(parts.iter()) {
+            for (i, x) in positions.iter().zip(model.restore(part)?.iter()) {
+                result[*i] = *x;
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{approximation_with, noisy_signal};
+    use crate::{BitTruncation, LinearQuantizer, Precision, Truncation};
+    use rscompress_transformation::{ByteShuffle, Lz4Block, Transform};
+
+    #[test]
+    fn test_fill_value() {
+        let data = [1.0f64, f64::NAN, 2.0, f64::INFINITY, f64::NAN, 1e300];
+        let mut model = MaskedApproximation::new(f64::NAN, LinearQuantizer::new(0.1));
+        let quantized = model.approximate(&data).unwrap();
+        assert_eq!(quantized.bins[..3], [5, MASKED, 10]);
+        assert_eq!(quantized.bins[4], MASKED);
+        assert_eq!(quantized.outliers, [f64::INFINITY, 1e300]);
+        let restored = approximation_with(&mut model, &data);
+        assert!(restored[1].is_nan() && restored[4].is_nan());
+        assert_eq!(restored[1].to_bits(), f64::NAN.to_bits());
+        assert_eq!(restored[3], f64::INFINITY);
+        assert_eq!(restored[5], 1e300);
+    }
+
+    #[test]
+    fn test_regions() {
+        let data = noisy_signal(1.0);
+        // region of interest in the middle with a tighter bound, land points without region
+        let regions: Vec<Option<usize>> = (0..data.len())
+            .map(|i| match i % 1_000 {
+                0..=99 => None,
+                400..=599 => Some(1),
+                _ => Some(0),
+            })
+            .collect();
+        let models = vec![LinearQuantizer::new(0.5), LinearQuantizer::new(1e-3)];
+        let mut model = MaskedApproximation::with_regions(-1.0, regions.clone(), models);
+        let quantized = model.approximate(&data).unwrap();
+        assert!(quantized.outliers.is_empty());
+        let restored = model.restore(&quantized).unwrap();
+        for ((x, y), region) in data.iter().zip(restored.iter()).zip(regions.iter()) {
+            match region {
+                None => assert_eq!(*y, -1.0),
+                Some(0) => assert!((x - y).abs() <= 0.5),
+                _ => assert!((x - y).abs() <= 1e-3),
+            }
+        }
+    }
+
+    #[test]
+    fn test_mask_compressibility() {
+        let data: Vec<f32> = noisy_signal(1e-2).iter().map(|x| *x as f32).collect();
+        let valid: Vec<bool> = (0..data.len()).map(|i| (i / 5_000) % 2 == 0).collect();
+        let compressed = |bins: &[i64]| {
+            let bytes: Vec<u8> = bins.iter().flat_map(|b| b.to_le_bytes()).collect();
+            let shuffled = ByteShuffle::new(8).transform(&bytes).unwrap();
+            Lz4Block::new().transform(&shuffled).unwrap().len()
+        };
+        let truncation = BitTruncation::new(Truncation::Rounding, Precision::Bits(10));
+        let mut full = MaskedApproximation::new(f32::NAN, truncation);
+        let mut masked = MaskedApproximation::with_mask(f32::NAN, &valid, truncation);
+        let all = compressed(&full.approximate(&data).unwrap().bins);
+        let quantized = masked.approximate(&data).unwrap();
+        let half = compressed(&quantized.bins);
+        // the valid half costs about `all / 2`, the masked half less than a fifth of that
+        assert!(10 * half < 6 * all, "{} vs {}", half, all);
+        let restored = masked.restore(&quantized).unwrap();
+        for (y, v) in restored.iter().zip(valid.iter()) {
+            assert_eq!(y.is_nan(), !*v);
+        }
+    }
+
+    #[test]
+    fn test_masked_bin_collision() {
+        // the bits of the negative smallest denormal equal the masked bin
+        let data = [-f64::from_bits(1), 1.0];
+        let mut model = MaskedApproximation::new(
+            0.0,
+            BitTruncation::new(Truncation::Shaving, Precision::Bits(52)),
+        );
+        let quantized = model.approximate(&data).unwrap();
+        assert_eq!(quantized.bins[0], OUTLIER);
+        assert_eq!(approximation_with(&mut model, &data), data);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut model =
+            MaskedApproximation::with_mask(0.0, &[true, false], LinearQuantizer::new(0.1));
+        assert!(matches!(
+            model.approximate(&[1.0f32]),
+            Err(ApproximationError::LengthMismatch(2, 1))
+        ));
+        let mut model = MaskedApproximation::with_regions(
+            0.0,
+            vec![Some(0), Some(2)],
+            vec![LinearQuantizer::new(0.1)],
+        );
+        assert!(matches!(
+            model.approximate(&[1.0f32, 2.0]),
+            Err(ApproximationError::InvalidRegion(2))
+        ));
+        let mut model =
+            MaskedApproximation::with_mask(0.0, &[true, false], LinearQuantizer::new(0.1));
+        let quantized = Quantized {
+            bins: vec![1, OUTLIER],
+            outliers: vec![5.0f32],
+        };
+        assert_eq!(model.restore(&quantized).unwrap(), [0.2, 0.0]);
+    }
+}
@@ -0,0 +1,192 @@
+//! Relative Quantization
+//!
+//! Quantization of floating point numbers in the logarithmic domain guaranteeing
+//! a point-wise relative error bound `|x - x'| <= theta * |x|`.
+use crate::{restore_with, Approximate, ApproximationError, Float, Quantized, OUTLIER};
+use log::debug;
+
+/// Bin of the positive zero
+pub const ZERO: i64 = i64::MAX;
+
+/// Largest logarithmic bin which is represented exactly by a `f64`
+const MAX_BIN: f64 = (1u64 << 52) as f64;
+
+/// Relative quantizer struct with the point-wise relative error bound `theta`
+///
+/// The logarithm of the magnitude is quantized with bins of width `2 * ln(1 + theta)`,
+/// which keeps the ratio `x' / x` within `[1 / (1 + theta), 1 + theta]`.
+/// The sign is saved in the lowest bit of the bin.
+/// Special values are handled explicitly:
+///
+/// - The positive zero is saved as [`ZERO`] bin and restored exactly.
+/// - The negative zero, infinities and NaN are stored as outliers.
+/// - Denormals and numbers close to the maximum are checked against the bound after
+///   rounding to the precision of the element type, and stored as outliers on violation.
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress_approximation::{Approximate, RelativeQuantizer};
+/// let mut model = RelativeQuantizer::new(0.01);
+/// let data = [1e-30f64, -5.0, 0.0, 1e30];
+/// let quantized = model.approximate(&data).unwrap();
+/// let restored = model.restore(&quantized).unwrap();
+/// for (x, y) in data.iter().zip(restored.iter()) {
+///     assert!((x - y).abs() <= 0.01 * x.abs());
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct RelativeQuantizer {
+    theta: f64,
+}
+
+impl RelativeQuantizer {
+    pub fn new(theta: f64) -> Self {
+        RelativeQuantizer { theta }
+    }
+    /// Relative error bound of the quantizer
+    pub fn theta(&self) -> f64 {
+        self.theta
+    }
+    fn step(&self) -> Result<f64, ApproximationError> {
+        let step = 2.0 * self.theta.ln_1p();
+        if !(self.theta > 0.0 && step > 0.0 && step.is_finite()) {
+            return Err(ApproximationError::InvalidThreshold(self.theta));
+        }
+        Ok(step)
+    }
+}
+
+/// Value of a logarithmic bin
+fn restore_bin(bin: i64, step: f64) -> f64 {
+    let magnitude = ((bin >> 1) as f64 * step).exp();
+    if bin & 1 == 1 {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+/// Implementation of the Approximate trait for Relative Quantization
+impl<T: Float> Approximate<T> for RelativeQuantizer {
+    fn approximate(&mut self, data: &[T]) -> Result<Quantized<T>, ApproximationError> {
+        let step = self.step()?;
+        let mut quantized = Quantized {
+            bins: Vec::with_capacity(data.len()),
+            outliers: Vec::new(),
+        };
+        for x in data.iter() {
+            let value = x.to_f64();
+            if value == 0.0 && value.is_sign_positive() {
+                quantized.bins.push(ZERO);
+                continue;
+            }
+            let k = (value.abs().ln() / step).round();
+            let bin = (k as i64) << 1 | value.is_sign_negative() as i64;
+            let restored = T::from_f64(restore_bin(bin, step)).to_f64();
+            if k.abs() < MAX_BIN && (value - restored).abs() <= self.theta * value.abs() {
+                quantized.bins.push(bin);
+            } else {
+                quantized.bins.push(OUTLIER);
+                quantized.outliers.push(*x);
+            }
+        }
+        debug!(
+            "Quantized {} elements with {} outliers",
+            data.len(),
+            quantized.outliers.len()
+        );
+        Ok(quantized)
+    }
+
+    fn restore(&mut self, quantized: &Quantized<T>) -> Result<Vec<T>, ApproximationError> {
+        let step = self.step()?;
+        restore_with(quantized, |bin| match bin {
+            ZERO => T::from_f64(0.0),
+            bin => T::from_f64(restore_bin(bin, step)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{approximation_with, config, normal_f32, relative_bound_with};
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_bins() {
+        let mut model = RelativeQuantizer::new(0.1);
+        let data = [1.0f64, -1.0, 0.0, 2.0, -0.5];
+        let quantized = model.approximate(&data).unwrap();
+        assert_eq!(quantized.bins[..3], [0, 1, ZERO]);
+        // ln(2) / (2 * ln(1.1)) ~ 3.64
+        assert_eq!(quantized.bins[3..], [8, -7]);
+        relative_bound_with(&mut model, &data, 0.1);
+    }
+
+    #[test]
+    fn test_special_values() {
+        let mut model = RelativeQuantizer::new(1e-3);
+        let data = [
+            0.0f32,
+            -0.0,
+            f32::NAN,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::MAX,
+            f32::MIN_POSITIVE,
+            f32::from_bits(1),
+            -f32::from_bits(0x0007_ffff),
+        ];
+        let quantized = model.approximate(&data).unwrap();
+        assert_eq!(quantized.bins[0], ZERO);
+        assert_eq!(quantized.bins[1..5], [OUTLIER; 4]);
+        let restored = approximation_with(&mut model, &data);
+        assert_eq!(restored[0].to_bits(), 0);
+        assert_eq!(restored[1].to_bits(), (-0.0f32).to_bits());
+        relative_bound_with(&mut model, &data, 1e-3);
+    }
+
+    #[test]
+    fn test_invalid_threshold() {
+        for theta in [0.0, -0.5, f64::NAN, f64::INFINITY].iter() {
+            let mut model = RelativeQuantizer::new(*theta);
+            assert!(matches!(
+                model.approximate(&[1.0f64]),
+                Err(ApproximationError::InvalidThreshold(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_normal_testdata() {
+        let data = normal_f32();
+        for theta in [1e-6, 1e-3, 0.1, 2.0].iter() {
+            let mut model = RelativeQuantizer::new(*theta);
+            relative_bound_with(&mut model, &data[..100_000], *theta);
+        }
+    }
+
+    proptest! {
+        #![proptest_config(config())]
+
+        #[test]
+        fn test_bound_on_testdata(start in 0usize..2_400_000, len in 0usize..4_096, theta in 1e-7f64..10.0) {
+            let data = normal_f32();
+            let end = (start + len).min(data.len());
+            let mut model = RelativeQuantizer::new(theta);
+            relative_bound_with(&mut model, &data[start..end], theta);
+        }
+
+        #[test]
+        fn test_bound_f32(data in prop::collection::vec(any::<f32>(), 0..256), theta in 1e-8f64..1e3) {
+            relative_bound_with(&mut RelativeQuantizer::new(theta), &data, theta);
+        }
+
+        #[test]
+        fn test_bound_f64(data in prop::collection::vec(any::<f64>(), 0..256), theta in 1e-15f64..1e3) {
+            relative_bound_with(&mut RelativeQuantizer::new(theta), &data, theta);
+        }
+    }
+}
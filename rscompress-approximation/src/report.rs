@@ -0,0 +1,354 @@
+//! Error Statistics
+//!
+//! Comparison of the original and the restored data of lossy round trips with
+//! the error metrics commonly used for the evaluation of lossy compressors
+//! (see e.g. [Z-checker](https://doi.org/10.1177/1094342017737147)).
+use crate::{ApproximationError, Float};
+use std::fmt;
+
+/// Size of the sliding window of the structural similarity
+const SSIM_WINDOW: usize = 7;
+
+/// Stabilization constants of the structural similarity relative to the value range
+const SSIM_K1: f64 = 0.01;
+const SSIM_K2: f64 = 0.03;
+
+/// Error statistics of the restored data with respect to the original data
+///
+/// Only elements whose original value is finite are compared. The errors are
+/// `restored - original` and the value range is the range of the original data.
+///
+/// - `max_abs_error` and `max_rel_error` are NaN, if a finite value is restored as NaN
+/// - `max_rel_error` is infinite, if a zero is restored as non-zero value
+/// - `nrmse` is the RMSE normalized by the value range
+/// - `psnr` is `20 * log10(range / rmse)` in decibel, infinite for exactly restored data
+/// - `pearson` is NaN, if the original or the restored data is constant
+/// - `ssim` is the mean structural similarity of all `xy` slices with `7x7` windows,
+///   if the data was compared as grid (see [`ErrorReport::with_grid`]). Its stabilization
+///   constants use a value range of 1, if the original data is constant
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress_approximation::ErrorReport;
+/// let report = ErrorReport::new(&[1.0f32, 2.0, 3.0, 4.0], &[1.5f32, 2.0, 3.0, 4.0]).unwrap();
+/// assert_eq!(report.max_abs_error, 0.5);
+/// assert_eq!(report.mean_error, 0.125);
+/// assert_eq!(report.rmse, 0.25);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorReport {
+    pub count: usize,
+    pub max_abs_error: f64,
+    pub max_rel_error: f64,
+    pub mean_error: f64,
+    pub rmse: f64,
+    pub nrmse: f64,
+    pub psnr: f64,
+    pub pearson: f64,
+    pub ssim: Option<f64>,
+}
+
+impl ErrorReport {
+    /// Error statistics of the data without spatial metrics
+    pub fn new<T: Float>(original: &[T], restored: &[T]) -> Result<Self, ApproximationError> {
+        if original.len() != restored.len() {
+            return Err(ApproximationError::LengthMismatch(
+                original.len(),
+                restored.len(),
+            ));
+        }
+        let pairs: Vec<(f64, f64)> = original
+            .iter()
+            .zip(restored.iter())
+            .map(|(x, y)| (x.to_f64(), y.to_f64()))
+            .filter(|(x, _)| x.is_finite())
+            .collect();
+        let count = pairs.len();
+        let n = count as f64;
+        let (mut max_abs_error, mut max_rel_error) = (0.0f64, 0.0f64);
+        let (mut min, mut max) = (f64::INFINITY, f64::NEG_INFINITY);
+        let (mut sum_error, mut sum_x, mut sum_y) = (0.0, 0.0, 0.0);
+        for (x, y) in pairs.iter() {
+            let error = y - x;
+            // `f64::max` ignores NaN, which would hide restored NaN values
+            if error.abs() > max_abs_error || error.is_nan() {
+                max_abs_error = error.abs();
+            }
+            let relative = match *x {
+                x if x != 0.0 => error.abs() / x.abs(),
+                _ if error == 0.0 => 0.0,
+                _ if error.is_nan() => f64::NAN,
+                _ => f64::INFINITY,
+            };
+            if relative > max_rel_error || relative.is_nan() {
+                max_rel_error = relative;
+            }
+            min = min.min(*x);
+            max = max.max(*x);
+            sum_error += error;
+            sum_x += x;
+            sum_y += y;
+        }
+        let (mean_x, mean_y) = (sum_x / n, sum_y / n);
+        let (mut squared_error, mut var_x, mut var_y, mut cov) = (0.0, 0.0, 0.0, 0.0);
+        for (x, y) in pairs.iter() {
+            squared_error += (y - x) * (y - x);
+            var_x += (x - mean_x) * (x - mean_x);
+            var_y += (y - mean_y) * (y - mean_y);
+            cov += (x - mean_x) * (y - mean_y);
+        }
+        let rmse = (squared_error / n).sqrt();
+        let range = max - min;
+        let (nrmse, psnr) = if rmse == 0.0 {
+            (0.0, f64::INFINITY)
+        } else {
+            (rmse / range, 20.0 * (range / rmse).log10())
+        };
+        Ok(ErrorReport {
+            count,
+            max_abs_error,
+            max_rel_error,
+            mean_error: sum_error / n,
+            rmse,
+            nrmse,
+            psnr,
+            pearson: cov / (var_x * var_y).sqrt(),
+            ssim: None,
+        })
+    }
+    /// Error statistics of gridded data including the structural similarity
+    ///
+    /// The data consists of `xy` slices with `nx * ny` elements, where `x` varies fastest.
+    /// The structural similarity is only calculated for windows with finite values.
+    /// It is `None`, if the slices are smaller than the window or contain no such window.
+    pub fn with_grid<T: Float>(
+        original: &[T],
+        restored: &[T],
+        nx: usize,
+        ny: usize,
+    ) -> Result<Self, ApproximationError> {
+        let mut report = Self::new(original, restored)?;
+        let slice = nx
+            .checked_mul(ny)
+            .ok_or(ApproximationError::LengthMismatch(
+                usize::MAX,
+                original.len(),
+            ))?;
+        if slice == 0 || !original.len().is_multiple_of(slice) {
+            return Err(ApproximationError::LengthMismatch(slice, original.len()));
+        }
+        let range = original
+            .iter()
+            .map(|x| x.to_f64())
+            .filter(|x| x.is_finite())
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), x| {
+                (min.min(x), max.max(x))
+            });
+        let range = range.1 - range.0;
+        let mut windows = 0usize;
+        let mut total = 0.0;
+        for (a, b) in original.chunks(slice).zip(restored.chunks(slice)) {
+            let (count, sum) = ssim(a, b, nx, ny, range);
+            windows += count;
+            total += sum;
+        }
+        report.ssim = if windows > 0 {
+            Some(total / windows as f64)
+        } else {
+            None
+        };
+        Ok(report)
+    }
+}
+
+/// Number of windows and sum of their structural similarity in a single slice
+fn ssim<T: Float>(
+    original: &[T],
+    restored: &[T],
+    nx: usize,
+    ny: usize,
+    range: f64,
+) -> (usize, f64) {
+    if nx < SSIM_WINDOW || ny < SSIM_WINDOW {
+        return (0, 0.0);
+    }
+    // constant data would otherwise compare constant windows as 0 / 0
+    let range = if range > 0.0 { range } else { 1.0 };
+    let c1 = (SSIM_K1 * range).powi(2);
+    let c2 = (SSIM_K2 * range).powi(2);
+    let n = (SSIM_WINDOW * SSIM_WINDOW) as f64;
+    let (mut count, mut total) = (0, 0.0);
+    for y0 in 0..=ny - SSIM_WINDOW {
+        for x0 in 0..=nx - SSIM_WINDOW {
+            let values = (y0..y0 + SSIM_WINDOW).flat_map(|y| {
+                (x0..x0 + SSIM_WINDOW).map(move |x| {
+                    let i = x + nx * y;
+                    (original[i].to_f64(), restored[i].to_f64())
+                })
+            });
+            let (mut sx, mut sy) = (0.0, 0.0);
+            let mut finite = true;
+            for (a, b) in values.clone() {
+                finite &= a.is_finite() && b.is_finite();
+                sx += a;
+                sy += b;
+            }
+            if !finite {
+                continue;
+            }
+            let (mx, my) = (sx / n, sy / n);
+            let (mut vx, mut vy, mut cov) = (0.0, 0.0, 0.0);
+            for (a, b) in values {
+                vx += (a - mx) * (a - mx);
+                vy += (b - my) * (b - my);
+                cov += (a - mx) * (b - my);
+            }
+            // sample covariances
+            let (vx, vy, cov) = (vx / (n - 1.0), vy / (n - 1.0), cov / (n - 1.0));
+            total += ((2.0 * mx * my + c1) * (2.0 * cov + c2))
+                / ((mx * mx + my * my + c1) * (vx + vy + c2));
+            count += 1;
+        }
+    }
+    (count, total)
+}
+
+impl fmt::Display for ErrorReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "max abs error {:.6e}, max rel error {:.6e}, mean error {:.6e}, rmse {:.6e}, nrmse {:.6e}, psnr {:.2} dB, pearson {:.6}",
+            self.max_abs_error,
+            self.max_rel_error,
+            self.mean_error,
+            self.rmse,
+            self.nrmse,
+            self.psnr,
+            self.pearson
+        )?;
+        if let Some(ssim) = self.ssim {
+            write!(f, ", ssim {:.6}", ssim)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{noisy_signal, normal_f32};
+    use crate::Approximate;
+    use crate::LinearQuantizer;
+
+    #[test]
+    fn test_statistics() {
+        let original = [0.0f64, 1.0, 2.0, 3.0, 4.0, f64::NAN];
+        let restored = [0.0f64, 1.5, 2.0, 2.5, 4.0, 7.0];
+        let report = ErrorReport::new(&original, &restored).unwrap();
+        assert_eq!(report.count, 5);
+        assert_eq!(report.max_abs_error, 0.5);
+        assert_eq!(report.max_rel_error, 0.5);
+        assert_eq!(report.mean_error, 0.0);
+        assert_eq!(report.rmse, 0.1f64.sqrt());
+        assert_eq!(report.nrmse, 0.1f64.sqrt() / 4.0);
+        assert!((report.psnr - 20.0 * (4.0 / 0.1f64.sqrt()).log10()).abs() < 1e-12);
+        assert!(report.pearson > 0.95 && report.pearson < 1.0);
+        assert_eq!(report.ssim, None);
+
+        let report = ErrorReport::new(&original, &original).unwrap();
+        assert_eq!(report.rmse, 0.0);
+        assert_eq!(report.psnr, f64::INFINITY);
+        assert!((report.pearson - 1.0).abs() < 1e-12);
+        let report = ErrorReport::new(&[0.0f32, 1.0], &[0.1f32, 1.0]).unwrap();
+        assert_eq!(report.max_rel_error, f64::INFINITY);
+        let negated: Vec<f64> = original.iter().map(|x| -x).collect();
+        let report = ErrorReport::new(&original[..5], &negated[..5]).unwrap();
+        assert!((report.pearson + 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_nan_restored() {
+        let original = [0.0f64, 1.0, 2.0, 3.0];
+        let report = ErrorReport::new(&original, &[0.0, f64::NAN, 2.0, 3.5]).unwrap();
+        assert!(report.max_abs_error.is_nan());
+        assert!(report.max_rel_error.is_nan());
+        let report = ErrorReport::new(&original, &[f64::NAN, 1.0, 2.0, 3.5]).unwrap();
+        assert!(report.max_abs_error.is_nan());
+        assert!(report.max_rel_error.is_nan());
+        assert!(report.to_string().contains("NaN"));
+    }
+
+    #[test]
+    fn test_bias() {
+        let data = noisy_signal(0.0);
+        let shifted: Vec<f64> = data.iter().map(|x| x + 0.25).collect();
+        let report = ErrorReport::new(&data, &shifted).unwrap();
+        assert!((report.mean_error - 0.25).abs() < 1e-9);
+        assert!((report.rmse - 0.25).abs() < 1e-9);
+        assert!((report.pearson - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_ssim() {
+        let (nx, ny) = (64, 48);
+        let field: Vec<f64> = (0..2 * nx * ny)
+            .map(|i| ((i % nx) as f64 * 0.2).sin() * ((i / nx) as f64 * 0.1).cos())
+            .collect();
+        let report = ErrorReport::with_grid(&field, &field, nx, ny).unwrap();
+        assert!((report.ssim.unwrap() - 1.0).abs() < 1e-12);
+        let mut previous = 1.0;
+        for theta in [1e-3, 1e-2, 0.1].iter() {
+            let mut model = LinearQuantizer::new(*theta);
+            let quantized = model.approximate(&field).unwrap();
+            let restored = model.restore(&quantized).unwrap();
+            let report = ErrorReport::with_grid(&field, &restored, nx, ny).unwrap();
+            let ssim = report.ssim.unwrap();
+            assert!(ssim < previous && ssim > 0.5, "{} {}", theta, ssim);
+            assert!(report.max_abs_error <= *theta);
+            previous = ssim;
+        }
+        let report = ErrorReport::with_grid(&field[..36], &field[..36], 6, 6).unwrap();
+        assert_eq!(report.ssim, None);
+        assert!(ErrorReport::with_grid(&field, &field, 7, 7).is_err());
+        assert!(matches!(
+            ErrorReport::with_grid(&field, &field, usize::MAX, 2),
+            Err(ApproximationError::LengthMismatch(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_ssim_constant() {
+        let zeros = [0.0f64; 64];
+        let report = ErrorReport::with_grid(&zeros, &zeros, 8, 8).unwrap();
+        assert_eq!(report.ssim, Some(1.0));
+        let shifted = [0.1f64; 64];
+        let report = ErrorReport::with_grid(&zeros, &shifted, 8, 8).unwrap();
+        let ssim = report.ssim.unwrap();
+        assert!(ssim > 0.0 && ssim < 0.5, "{}", ssim);
+    }
+
+    #[test]
+    fn test_testdata() {
+        let data = &normal_f32()[..100_000];
+        let mut model = LinearQuantizer::new(0.5);
+        let quantized = model.approximate(data).unwrap();
+        let restored = model.restore(&quantized).unwrap();
+        let report = ErrorReport::with_grid(data, &restored, 100, 1_000).unwrap();
+        assert!(report.max_abs_error <= 0.5);
+        // uniform quantization error of width 1 has a rmse of 1 / sqrt(12)
+        assert!((report.rmse - 12f64.sqrt().recip()).abs() < 1e-2);
+        assert!(report.mean_error.abs() < 1e-2);
+        assert!(report.pearson > 0.999);
+        assert!(report.ssim.unwrap() > 0.99);
+        assert!(report.to_string().contains("psnr"));
+    }
+
+    #[test]
+    fn test_length_mismatch() {
+        assert!(matches!(
+            ErrorReport::new(&[1.0f32, 2.0], &[1.0f32]),
+            Err(ApproximationError::LengthMismatch(2, 1))
+        ));
+    }
+}
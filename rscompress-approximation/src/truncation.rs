@@ -0,0 +1,276 @@
+//! Precision Truncation
+//!
+//! Approximation of floating point numbers by keeping only the leading bits of the mantissa.
+//! The discarded bits are set to constant values, which makes the data compress well
+//! with lossless transformations and codings.
+//! See [Zender (2016)](https://doi.org/10.5194/gmd-9-3199-2016) for bit shaving and grooming
+//! and [Klöwer et al. (2021)](https://doi.org/10.1038/s43588-021-00156-2) for bit rounding.
+use crate::{keepbits, restore_with, Approximate, ApproximationError, Float, Quantized, OUTLIER};
+use log::debug;
+
+/// Methods for setting the discarded mantissa bits
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Truncation {
+    /// Set all discarded bits to zero
+    Shaving,
+    /// Alternately set the discarded bits to zero and one, which avoids a bias towards zero
+    Grooming,
+    /// Round to the nearest number with the kept bits, ties to even (IEEE default)
+    Rounding,
+}
+
+/// Precision of the truncated numbers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Precision {
+    /// Number of kept mantissa bits
+    Bits(u32),
+    /// Number of significant decimal digits
+    Digits(u32),
+    /// Share of the real information of the data to be kept (see [`keepbits`])
+    Information(f64),
+}
+
+impl Precision {
+    /// Number of kept mantissa bits for the data
+    ///
+    /// Decimal digits `d` keep `ceil(d * log2(10))` bits, such that the relative error
+    /// of the shaved numbers is below `10^-d`.
+    pub fn keepbits<T: Float>(&self, data: &[T]) -> u32 {
+        let bits = match *self {
+            Precision::Bits(bits) => bits,
+            Precision::Digits(digits) => (digits as f64 * std::f64::consts::LOG2_10).ceil() as u32,
+            Precision::Information(level) => keepbits(data, level),
+        };
+        bits.min(T::MANTISSA_BITS)
+    }
+}
+
+/// Precision truncation struct with the method for the discarded bits and the precision
+///
+/// Shaved and groomed numbers keep a relative error below `2^-keepbits`,
+/// rounded numbers keep a relative error of at most `2^-(keepbits + 1)`.
+/// Denormals have less significant bits and keep only the absolute error of the smallest normal number.
+/// Zeros, infinities and NaN are not changed.
+/// Numbers which would be rounded to infinity are shaved instead.
+/// The bins of the approximation are the bit representations of the truncated numbers.
+/// The negative zero of `f64`, whose bits equal the [`OUTLIER`] bin, is stored as outlier.
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress_approximation::{BitTruncation, Precision, Truncation};
+/// let model = BitTruncation::new(Truncation::Rounding, Precision::Bits(2));
+/// assert_eq!(model.truncate(&[1.3f32, 1.4, -7.0]), [1.25, 1.5, -7.0]);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct BitTruncation {
+    truncation: Truncation,
+    precision: Precision,
+}
+
+impl BitTruncation {
+    pub fn new(truncation: Truncation, precision: Precision) -> Self {
+        BitTruncation {
+            truncation,
+            precision,
+        }
+    }
+    /// Truncate the numbers to the precision
+    pub fn truncate<T: Float>(&self, data: &[T]) -> Vec<T> {
+        let keepbits = self.precision.keepbits(data);
+        let discard = T::MANTISSA_BITS - keepbits;
+        debug!("Keeping {} of {} mantissa bits", keepbits, T::MANTISSA_BITS);
+        if discard == 0 {
+            return data.to_vec();
+        }
+        let tail = (1u64 << discard) - 1;
+        data.iter()
+            .enumerate()
+            .map(|(i, x)| {
+                let value = x.to_f64();
+                if value == 0.0 || !value.is_finite() {
+                    return *x;
+                }
+                let bits = x.to_bits64();
+                let shaved = T::from_bits64(bits & !tail);
+                match self.truncation {
+                    Truncation::Shaving => shaved,
+                    Truncation::Grooming if i % 2 == 1 => T::from_bits64(bits | tail),
+                    Truncation::Grooming => shaved,
+                    Truncation::Rounding => {
+                        let odd = bits >> discard & 1;
+                        let rounded = T::from_bits64((bits + (tail >> 1) + odd) & !tail);
+                        if rounded.to_f64().is_finite() {
+                            rounded
+                        } else {
+                            shaved
+                        }
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// Implementation of the Approximate trait for Precision Truncation
+impl<T: Float> Approximate<T> for BitTruncation {
+    fn approximate(&mut self, data: &[T]) -> Result<Quantized<T>, ApproximationError> {
+        let mut quantized = Quantized {
+            bins: Vec::with_capacity(data.len()),
+            outliers: Vec::new(),
+        };
+        for x in self.truncate(data).iter() {
+            // the bits of the negative zero of `f64` are the outlier bin
+            let bin = x.to_bits64() as i64;
+            if bin == OUTLIER {
+                quantized.outliers.push(*x);
+            }
+            quantized.bins.push(bin);
+        }
+        Ok(quantized)
+    }
+
+    fn restore(&mut self, quantized: &Quantized<T>) -> Result<Vec<T>, ApproximationError> {
+        restore_with(quantized, |bin| T::from_bits64(bin as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{approximation_with, config, noisy_signal, normal_f32};
+    use proptest::prelude::*;
+    use rscompress_transformation::{ByteShuffle, Lz4Block, Transform};
+
+    const TRUNCATIONS: [Truncation; 3] = [
+        Truncation::Shaving,
+        Truncation::Grooming,
+        Truncation::Rounding,
+    ];
+
+    /// Check the relative error bound of the normal numbers
+    fn bound_with<T: Float>(truncation: Truncation, data: &[T], keepbits: u32) {
+        let mut model = BitTruncation::new(truncation, Precision::Bits(keepbits));
+        let result = approximation_with(&mut model, data);
+        let theta = match truncation {
+            Truncation::Rounding => 0.5f64.powi(keepbits as i32 + 1),
+            _ => 0.5f64.powi(keepbits as i32),
+        };
+        let exponent = |x: &T| x.to_bits64() << (65 - T::BITS) >> (65 - T::BITS + T::MANTISSA_BITS);
+        for (x, y) in data.iter().zip(result.iter()) {
+            let normal = exponent(x) != 0;
+            let (x, y) = (x.to_f64(), y.to_f64());
+            if x.is_finite() && normal {
+                assert!((x - y).abs() <= theta * x.abs(), "|{} - {}|", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn test_shaving_and_grooming() {
+        let data = [1.3f32, 1.3, 1.3, -1.3];
+        let model = BitTruncation::new(Truncation::Shaving, Precision::Bits(2));
+        assert_eq!(model.truncate(&data), [1.25, 1.25, 1.25, -1.25]);
+        let model = BitTruncation::new(Truncation::Grooming, Precision::Bits(2));
+        let groomed = model.truncate(&data);
+        assert_eq!(groomed[0], 1.25);
+        assert_eq!(groomed[1].to_bits(), 0x3fbf_ffff);
+        assert_eq!(groomed[3].to_bits(), 0xbfbf_ffff);
+    }
+
+    #[test]
+    fn test_rounding() {
+        let model = BitTruncation::new(Truncation::Rounding, Precision::Bits(1));
+        // ties to even
+        assert_eq!(
+            model.truncate(&[1.25f64, 1.75, 1.8, -1.25]),
+            [1.0, 2.0, 2.0, -1.0]
+        );
+        let model = BitTruncation::new(Truncation::Rounding, Precision::Bits(0));
+        assert_eq!(
+            model.truncate(&[f32::MAX, 3.0, 0.0]),
+            [2f32.powi(127), 2.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn test_special_values() {
+        for truncation in TRUNCATIONS.iter() {
+            let model = BitTruncation::new(*truncation, Precision::Bits(3));
+            let data = [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, 0.0, -0.0];
+            let result = model.truncate(&data);
+            assert!(result[0].is_nan());
+            assert_eq!(result[1..3], data[1..3]);
+            assert_eq!(result[3].to_bits(), 0);
+            assert_eq!(result[4].to_bits(), (-0.0f64).to_bits());
+            let restored = approximation_with(&mut model.clone(), &data);
+            assert_eq!(restored[4].to_bits(), (-0.0f64).to_bits());
+        }
+    }
+
+    #[test]
+    fn test_precision() {
+        assert_eq!(Precision::Digits(3).keepbits::<f32>(&[]), 10);
+        assert_eq!(Precision::Digits(7).keepbits::<f32>(&[]), 23);
+        assert_eq!(Precision::Digits(7).keepbits::<f64>(&[]), 24);
+        assert_eq!(Precision::Bits(60).keepbits::<f64>(&[]), 52);
+        let mut runs = vec![1.0f32; 1_000];
+        runs.extend_from_slice(&[1.5; 1_000]);
+        assert_eq!(Precision::Information(0.99).keepbits(&runs), 1);
+        let data = [3.25f32, 0.123_4, -1_234.5];
+        let model = BitTruncation::new(Truncation::Shaving, Precision::Digits(3));
+        for (x, y) in data.iter().zip(model.truncate(&data).iter()) {
+            assert!((x - y).abs() < 1e-3 * x.abs());
+        }
+        let model = BitTruncation::new(Truncation::Rounding, Precision::Bits(23));
+        assert_eq!(model.truncate(&data), data);
+    }
+
+    #[test]
+    fn test_compressibility() {
+        let data = &normal_f32()[..100_000];
+        let compressed = |values: &[f32]| {
+            let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+            let shuffled = ByteShuffle::new(4).transform(&bytes).unwrap();
+            Lz4Block::new().transform(&shuffled).unwrap().len()
+        };
+        let original = compressed(data);
+        for truncation in TRUNCATIONS.iter() {
+            let model = BitTruncation::new(*truncation, Precision::Bits(7));
+            let truncated = compressed(&model.truncate(data));
+            assert!(2 * truncated < original, "{} vs {}", truncated, original);
+            bound_with(*truncation, data, 7);
+        }
+    }
+
+    #[test]
+    fn test_information_rounding() {
+        let data: Vec<f32> = noisy_signal(1e-2).iter().map(|v| *v as f32).collect();
+        let mut model = BitTruncation::new(Truncation::Rounding, Precision::Information(0.99));
+        let restored = approximation_with(&mut model, &data);
+        let kept = Precision::Information(0.99).keepbits(&data);
+        assert!(kept < 20, "{}", kept);
+        for (x, y) in data.iter().zip(restored.iter()) {
+            assert!((x - y).abs() <= 0.5f32.powi(kept as i32 + 1) * x.abs());
+        }
+        assert_eq!(model.truncate(&restored), restored);
+    }
+
+    proptest! {
+        #![proptest_config(config())]
+
+        #[test]
+        fn test_bound_f32(data in prop::collection::vec(any::<f32>(), 0..256), keepbits in 0u32..24) {
+            for truncation in TRUNCATIONS.iter() {
+                bound_with(*truncation, &data, keepbits);
+            }
+        }
+
+        #[test]
+        fn test_bound_f64(data in prop::collection::vec(any::<f64>(), 0..256), keepbits in 0u32..53) {
+            for truncation in TRUNCATIONS.iter() {
+                bound_with(*truncation, &data, keepbits);
+            }
+        }
+    }
+}
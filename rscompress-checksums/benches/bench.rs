@@ -1,6 +1,6 @@
 use criterion::{criterion_group, criterion_main};
 use criterion::{BenchmarkId, Criterion, Throughput};
-use rscompress_checksums::{Adler32, Checksum, CRC32};
+use rscompress_checksums::{Adler32, Checksum, CRC32, XXH32};
 
 const MIN_DATA_SIZE: usize = 1_000;
 const FACTORS: [usize; 5] = [1, 5, 10, 50, 100];
@@ -10,7 +10,7 @@ fn criterion_benchmark(c: &mut Criterion) {
     let source = include_bytes!("../../testdata/enwik7.raw");
     for factor in FACTORS.iter() {
         let size = factor * MIN_DATA_SIZE;
-        let data: Vec<u8> = source.iter().take(size).map(|x| *x).collect();
+        let data: Vec<u8> = source.iter().take(size).copied().collect();
         let mut tmp = 0u32;
         group.throughput(Throughput::Bytes(size as u64));
 
@@ -21,17 +21,26 @@ fn criterion_benchmark(c: &mut Criterion) {
             data.as_slice(),
             |b, s| {
                 b.iter(|| {
-                    model.update(&s);
+                    model.update(s);
                     tmp = model.checksum().unwrap()
                 });
             },
         );
 
-        // Testing Adler32 checksums
+        // Testing CRC32 checksums
         let mut model = CRC32::new();
         group.bench_with_input(BenchmarkId::new("CRC32", size), data.as_slice(), |b, s| {
             b.iter(|| {
-                model.update(&s);
+                model.update(s);
+                tmp = model.checksum().unwrap()
+            });
+        });
+
+        // Testing XXH32 checksums
+        let mut model = XXH32::new();
+        group.bench_with_input(BenchmarkId::new("XXH32", size), data.as_slice(), |b, s| {
+            b.iter(|| {
+                model.update(s);
                 tmp = model.checksum().unwrap()
             });
         });
@@ -9,9 +9,11 @@ use std::fmt;
 
 mod adler32;
 mod crc32;
+mod xxh32;
 
 pub use adler32::Adler32;
 pub use crc32::CRC32;
+pub use xxh32::XXH32;
 
 /// Trait for calculating checksums from binary data
 pub trait Checksum {
@@ -56,7 +58,7 @@ pub mod tests {
     /// Helper function for calculating checksum
     pub fn checksum<M: Checksum + Default>(input: &[u8], expected: u32) {
         let mut model: M = Default::default();
-        model.update(&input);
+        model.update(input);
         assert_eq!(model.checksum().unwrap(), expected)
     }
 }
@@ -0,0 +1,163 @@
+//! XXH32 checksum
+//!
+//! Implementation of the 32 bit xxHash algorithm as described [here](https://github.com/Cyan4973/xxHash/blob/dev/doc/xxhash_spec.md).
+//! It is used e.g. for the content and block checksums of the LZ4 frame format.
+use super::{Checksum, ChecksumError};
+use log::{debug, info};
+
+const PRIME32_1: u32 = 0x9E37_79B1;
+const PRIME32_2: u32 = 0x85EB_CA77;
+const PRIME32_3: u32 = 0xC2B2_AE3D;
+const PRIME32_4: u32 = 0x27D4_EB2F;
+const PRIME32_5: u32 = 0x1656_67B1;
+
+/// XXH32 struct to save the accumulators and the not yet processed bytes
+#[derive(Debug)]
+pub struct XXH32 {
+    seed: u32,
+    acc: [u32; 4],
+    buffer: Vec<u8>,
+    total: u64,
+}
+
+fn round(acc: u32, lane: u32) -> u32 {
+    acc.wrapping_add(lane.wrapping_mul(PRIME32_2))
+        .rotate_left(13)
+        .wrapping_mul(PRIME32_1)
+}
+
+/// Process a stripe of 16 bytes with the four accumulators
+fn consume(acc: &mut [u32; 4], stripe: &[u8]) {
+    for (i, a) in acc.iter_mut().enumerate() {
+        *a = round(*a, read_u32(&stripe[4 * i..]));
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+impl XXH32 {
+    /// Generate new XXH32 struct with seed 0
+    pub fn new() -> Self {
+        Self::with_seed(0)
+    }
+    /// Generate new XXH32 struct with the given seed
+    pub fn with_seed(seed: u32) -> Self {
+        info!("New XXH32 checksum with seed {}", seed);
+        XXH32 {
+            seed,
+            acc: [
+                seed.wrapping_add(PRIME32_1).wrapping_add(PRIME32_2),
+                seed.wrapping_add(PRIME32_2),
+                seed,
+                seed.wrapping_sub(PRIME32_1),
+            ],
+            buffer: Vec::with_capacity(16),
+            total: 0,
+        }
+    }
+}
+
+/// Use the new function for generating the default implementation
+impl Default for XXH32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Implementation of the Checksum trait for XXH32
+impl Checksum for XXH32 {
+    fn update(&mut self, data: &[u8]) -> Option<usize> {
+        let len = data.len();
+        self.total += len as u64;
+        let mut data = data;
+        if !self.buffer.is_empty() {
+            let missing = (16 - self.buffer.len()).min(data.len());
+            self.buffer.extend_from_slice(&data[..missing]);
+            data = &data[missing..];
+            if self.buffer.len() < 16 {
+                return Some(len);
+            }
+            consume(&mut self.acc, &self.buffer);
+            self.buffer.clear();
+        }
+        let mut stripes = data.chunks_exact(16);
+        for stripe in &mut stripes {
+            consume(&mut self.acc, stripe);
+        }
+        self.buffer.extend_from_slice(stripes.remainder());
+        debug!("XXH32 Update: {} bytes, New State: {:?}", len, self);
+        Some(len)
+    }
+    fn checksum(&self) -> Result<u32, ChecksumError> {
+        let mut result = if self.total >= 16 {
+            self.acc[0]
+                .rotate_left(1)
+                .wrapping_add(self.acc[1].rotate_left(7))
+                .wrapping_add(self.acc[2].rotate_left(12))
+                .wrapping_add(self.acc[3].rotate_left(18))
+        } else {
+            self.seed.wrapping_add(PRIME32_5)
+        };
+        result = result.wrapping_add(self.total as u32);
+        let mut lanes = self.buffer.chunks_exact(4);
+        for lane in &mut lanes {
+            result = result
+                .wrapping_add(read_u32(lane).wrapping_mul(PRIME32_3))
+                .rotate_left(17)
+                .wrapping_mul(PRIME32_4);
+        }
+        for byte in lanes.remainder() {
+            result = result
+                .wrapping_add((*byte as u32).wrapping_mul(PRIME32_5))
+                .rotate_left(11)
+                .wrapping_mul(PRIME32_1);
+        }
+        result ^= result >> 15;
+        result = result.wrapping_mul(PRIME32_2);
+        result ^= result >> 13;
+        result = result.wrapping_mul(PRIME32_3);
+        result ^= result >> 16;
+        info!("XXH32 Checksum: {}", result);
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::checksum;
+
+    #[test]
+    fn test_words() {
+        checksum::<XXH32>("".as_bytes(), 0x02CC5D05);
+        checksum::<XXH32>("a".as_bytes(), 0x550D7456);
+        checksum::<XXH32>("abc".as_bytes(), 0x32D153FF);
+        checksum::<XXH32>(
+            "Nobody inspects the spammish repetition".as_bytes(),
+            0xE2293B2F,
+        );
+    }
+
+    #[test]
+    fn test_seed() {
+        let mut model = XXH32::with_seed(1);
+        model.update("".as_bytes());
+        assert_eq!(model.checksum().unwrap(), 0x0B2CB792);
+    }
+
+    #[test]
+    fn test_chunked_updates() {
+        let data: Vec<u8> = (0..1_000u32).map(|x| (x * 7 % 256) as u8).collect();
+        let mut whole = XXH32::new();
+        whole.update(&data);
+        for size in [1, 3, 15, 16, 17, 100].iter() {
+            let mut model = XXH32::new();
+            for chunk in data.chunks(*size) {
+                model.update(chunk);
+            }
+            assert_eq!(model.checksum().unwrap(), whole.checksum().unwrap());
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Bit streams
+//!
+//! Writing and reading of values with an arbitrary number of bits.
+//! The bits are packed into bytes starting with the most significant bit.
+use crate::CodingError;
+
+/// Writer packing values bitwise into bytes
+#[derive(Debug, Default)]
+pub struct BitWriter {
+    bytes: Vec<u8>,
+    current: u8,
+    filled: u32,
+}
+
+impl BitWriter {
+    pub fn new() -> Self {
+        Default::default()
+    }
+    /// Write the lowest `n` bits of `value` (at most 64)
+    pub fn write(&mut self, value: u64, n: u32) {
+        for i in (0..n).rev() {
+            self.current = (self.current << 1) | ((value >> i) & 1) as u8;
+            self.filled += 1;
+            if self.filled == 8 {
+                self.bytes.push(self.current);
+                self.current = 0;
+                self.filled = 0;
+            }
+        }
+    }
+    /// Write a single bit
+    pub fn write_bit(&mut self, bit: bool) {
+        self.write(bit as u64, 1)
+    }
+    /// Number of bits written so far
+    pub fn bits_written(&self) -> usize {
+        8 * self.bytes.len() + self.filled as usize
+    }
+    /// Pad the last byte with zeros and return all bytes
+    pub fn finish(mut self) -> Vec<u8> {
+        if self.filled > 0 {
+            self.bytes.push(self.current << (8 - self.filled));
+        }
+        self.bytes
+    }
+}
+
+/// Reader for bitwise packed values
+#[derive(Debug)]
+pub struct BitReader<'a> {
+    source: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(source: &'a [u8]) -> Self {
+        BitReader { source, pos: 0 }
+    }
+    /// Read `n` bits (at most 64) as the lowest bits of the result
+    pub fn read(&mut self, n: u32) -> Result<u64, CodingError> {
+        let mut value = 0u64;
+        for _ in 0..n {
+            let byte = self
+                .source
+                .get(self.pos / 8)
+                .ok_or(CodingError::TruncatedBuffer)?;
+            value = (value << 1) | ((byte >> (7 - self.pos % 8)) & 1) as u64;
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+    /// Read a single bit
+    pub fn read_bit(&mut self) -> Result<bool, CodingError> {
+        Ok(self.read(1)? == 1)
+    }
+    /// Skip `n` bits
+    pub fn skip(&mut self, n: usize) {
+        self.pos += n;
+    }
+    /// Number of bits read or skipped so far
+    pub fn bits_read(&self) -> usize {
+        self.pos
+    }
+    /// Number of bytes touched so far
+    pub fn bytes_read(&self) -> usize {
+        self.pos.div_ceil(8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bits_roundtrip() {
+        let mut writer = BitWriter::new();
+        writer.write_bit(true);
+        writer.write(0b101, 3);
+        writer.write(u64::MAX, 64);
+        writer.write(0, 0);
+        writer.write(2, 2);
+        assert_eq!(writer.bits_written(), 70);
+        let bytes = writer.finish();
+        assert_eq!(bytes.len(), 9);
+        assert_eq!(bytes[0], 0b1101_1111);
+
+        let mut reader = BitReader::new(&bytes);
+        assert!(reader.read_bit().unwrap());
+        assert_eq!(reader.read(3).unwrap(), 0b101);
+        assert_eq!(reader.read(64).unwrap(), u64::MAX);
+        assert_eq!(reader.read(2).unwrap(), 2);
+        assert_eq!(reader.bytes_read(), 9);
+        assert!(reader.read(8).is_err());
+
+        let mut reader = BitReader::new(&bytes);
+        reader.skip(4);
+        assert_eq!(reader.read(4).unwrap(), 0b1111);
+        assert_eq!(reader.bits_read(), 8);
+    }
+}
@@ -0,0 +1,265 @@
+//! Huffman Coding
+//!
+//! Implementation of canonical [Huffman coding](https://en.wikipedia.org/wiki/Canonical_Huffman_code)
+//! for symbols of a large and sparse alphabet, e.g. the quantization bins of lossy compressors.
+use crate::bits::{BitReader, BitWriter};
+use crate::{Coding, CodingError};
+use log::debug;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Maximum length of a code in bits
+pub const MAX_CODE_LENGTH: u32 = 32;
+
+/// Huffman struct for coding symbols with a canonical Huffman code
+///
+/// The code is built for each call of `encode` and saved in front of the data:
+///
+/// - number of symbols (`u64`, little-endian)
+/// - number of distinct symbols (`u32`, little-endian)
+/// - distinct symbols (`u32`, little-endian) and their code lengths (`u8`),
+///   sorted by code length and symbol
+/// - codes of the symbols, packed starting with the most significant bit
+///
+/// Code lengths are limited to [`MAX_CODE_LENGTH`] by flattening the frequencies.
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress_coding::{Coding, Huffman};
+/// let mut model = Huffman::new();
+/// let symbols = [7, 7, 7, 7, 1_000_000, 7, 3, 7];
+/// let encoded = model.encode(&symbols).unwrap();
+/// assert_eq!(model.decode(&encoded).unwrap(), symbols);
+/// ```
+#[derive(Debug, Default)]
+pub struct Huffman {}
+
+impl Huffman {
+    pub fn new() -> Self {
+        Huffman {}
+    }
+}
+
+/// Code lengths of the symbols for the given frequencies
+fn code_lengths(frequencies: &[(u32, usize)]) -> Vec<u32> {
+    if frequencies.len() < 2 {
+        return vec![1; frequencies.len()];
+    }
+    let mut weights: Vec<usize> = frequencies.iter().map(|(_, f)| *f).collect();
+    loop {
+        // nodes are leaves (index < n) or inner nodes, parents point to the inner nodes
+        let n = weights.len();
+        let mut parents = vec![0usize; 2 * n - 1];
+        let mut heap: BinaryHeap<Reverse<(usize, usize)>> = weights
+            .iter()
+            .enumerate()
+            .map(|(i, w)| Reverse((*w, i)))
+            .collect();
+        let mut next = n;
+        while let (Some(Reverse((wa, a))), Some(Reverse((wb, b)))) = (heap.pop(), heap.pop()) {
+            parents[a] = next;
+            parents[b] = next;
+            heap.push(Reverse((wa + wb, next)));
+            next += 1;
+        }
+        let mut depths = vec![0u32; 2 * n - 1];
+        for node in (0..2 * n - 2).rev() {
+            depths[node] = depths[parents[node]] + 1;
+        }
+        let lengths: Vec<u32> = depths[..n].to_vec();
+        if lengths.iter().all(|l| *l <= MAX_CODE_LENGTH) {
+            return lengths;
+        }
+        for w in weights.iter_mut() {
+            *w = (*w >> 1).max(1);
+        }
+    }
+}
+
+/// Canonical codes of the symbols sorted by code length and symbol
+fn canonical_codes(table: &[(u32, u32)]) -> Vec<u64> {
+    let mut codes = Vec::with_capacity(table.len());
+    let mut code = 0u64;
+    let mut previous = table.first().map(|(_, l)| *l).unwrap_or(0);
+    for (_, length) in table.iter() {
+        code <<= length - previous;
+        codes.push(code);
+        code += 1;
+        previous = *length;
+    }
+    codes
+}
+
+/// Implementation of the Coding trait for Huffman
+impl Coding for Huffman {
+    fn encode(&mut self, symbols: &[u32]) -> Result<Vec<u8>, CodingError> {
+        let mut counts: HashMap<u32, usize> = HashMap::new();
+        for s in symbols.iter() {
+            *counts.entry(*s).or_insert(0) += 1;
+        }
+        let mut frequencies: Vec<(u32, usize)> = counts.into_iter().collect();
+        frequencies.sort_unstable();
+        let lengths = code_lengths(&frequencies);
+        let mut table: Vec<(u32, u32)> = frequencies
+            .iter()
+            .zip(lengths.iter())
+            .map(|((s, _), l)| (*s, *l))
+            .collect();
+        table.sort_unstable_by_key(|(s, l)| (*l, *s));
+        let codes = canonical_codes(&table);
+        let lookup: HashMap<u32, (u64, u32)> = table
+            .iter()
+            .zip(codes.iter())
+            .map(|((s, l), c)| (*s, (*c, *l)))
+            .collect();
+
+        let mut result: Vec<u8> = Vec::with_capacity(12 + 5 * table.len() + symbols.len() / 2);
+        result.extend_from_slice(&(symbols.len() as u64).to_le_bytes());
+        result.extend_from_slice(&(table.len() as u32).to_le_bytes());
+        for (s, l) in table.iter() {
+            result.extend_from_slice(&s.to_le_bytes());
+            result.push(*l as u8);
+        }
+        let mut writer = BitWriter::new();
+        for s in symbols.iter() {
+            let (code, length) = lookup[s];
+            writer.write(code, length);
+        }
+        result.extend_from_slice(&writer.finish());
+        debug!(
+            "Huffman coded {} symbols of {} distinct into {} bytes",
+            symbols.len(),
+            table.len(),
+            result.len()
+        );
+        Ok(result)
+    }
+
+    fn decode(&mut self, source: &[u8]) -> Result<Vec<u32>, CodingError> {
+        let header = source.get(..12).ok_or(CodingError::TruncatedBuffer)?;
+        let mut n = [0u8; 8];
+        n.copy_from_slice(&header[..8]);
+        let n = u64::from_le_bytes(n) as usize;
+        let mut m = [0u8; 4];
+        m.copy_from_slice(&header[8..]);
+        let m = u32::from_le_bytes(m) as usize;
+        let entries = source
+            .get(12..12 + 5 * m)
+            .ok_or(CodingError::TruncatedBuffer)?;
+        let table: Vec<(u32, u32)> = entries
+            .chunks_exact(5)
+            .map(|e| (u32::from_le_bytes([e[0], e[1], e[2], e[3]]), e[4] as u32))
+            .collect();
+        if n > 0 && m == 0 {
+            return Err(CodingError::InvalidTable);
+        }
+        for pair in table.windows(2) {
+            if pair[0].1 > pair[1].1 {
+                return Err(CodingError::InvalidTable);
+            }
+        }
+        if table.iter().any(|(_, l)| *l == 0 || *l > MAX_CODE_LENGTH) {
+            return Err(CodingError::InvalidTable);
+        }
+        let codes = canonical_codes(&table);
+        // first code and position in the table of each code length
+        let mut first: Vec<Option<(u64, usize)>> = vec![None; MAX_CODE_LENGTH as usize + 1];
+        let mut count = vec![0usize; MAX_CODE_LENGTH as usize + 1];
+        for (i, ((_, l), c)) in table.iter().zip(codes.iter()).enumerate() {
+            if *c >> l != 0 {
+                return Err(CodingError::InvalidTable);
+            }
+            first[*l as usize].get_or_insert((*c, i));
+            count[*l as usize] += 1;
+        }
+
+        let mut reader = BitReader::new(&source[12 + 5 * m..]);
+        let mut result: Vec<u32> = Vec::with_capacity(n.min(8 * source.len()));
+        for _ in 0..n {
+            let mut code = 0u64;
+            let mut length = 0usize;
+            loop {
+                code = code << 1 | reader.read_bit()? as u64;
+                length += 1;
+                if length > MAX_CODE_LENGTH as usize {
+                    return Err(CodingError::InvalidCode);
+                }
+                if let Some((c, i)) = first[length] {
+                    if code >= c && code - c < count[length] as u64 {
+                        result.push(table[i + (code - c) as usize].0);
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{decode, roundtrip};
+
+    #[test]
+    fn test_canonical_codes() {
+        let table = [(5, 1), (2, 2), (0, 3), (9, 3)];
+        assert_eq!(canonical_codes(&table), [0b0, 0b10, 0b110, 0b111]);
+        let frequencies = [(0, 1), (2, 2), (5, 8), (9, 1)];
+        assert_eq!(code_lengths(&frequencies), [3, 2, 1, 3]);
+    }
+
+    #[test]
+    fn test_encode() {
+        let encoded = Huffman::new().encode(&[5, 5, 2, 5]).unwrap();
+        assert_eq!(encoded[..8], 4u64.to_le_bytes());
+        assert_eq!(encoded[8..12], 2u32.to_le_bytes());
+        assert_eq!(encoded[12..22], [2, 0, 0, 0, 1, 5, 0, 0, 0, 1]);
+        assert_eq!(encoded[22..], [0b1101_0000]);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        roundtrip::<Huffman>(&[]);
+        roundtrip::<Huffman>(&[42]);
+        roundtrip::<Huffman>(&[u32::MAX; 100]);
+        roundtrip::<Huffman>(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 0, 0]);
+        let skewed: Vec<u32> = (0..10_000u32).map(|i| i.trailing_zeros()).collect();
+        roundtrip::<Huffman>(&skewed);
+    }
+
+    #[test]
+    fn test_length_limit() {
+        // fibonacci frequencies result in the most unbalanced trees
+        let (mut a, mut b) = (1usize, 1usize);
+        let mut frequencies = Vec::new();
+        for s in 0..60u32 {
+            frequencies.push((s, a));
+            let c = a + b;
+            a = b;
+            b = c;
+        }
+        let lengths = code_lengths(&frequencies);
+        assert!(lengths.iter().all(|l| *l <= MAX_CODE_LENGTH));
+        let kraft: f64 = lengths.iter().map(|l| 0.5f64.powi(*l as i32)).sum();
+        assert!(kraft <= 1.0);
+        assert_eq!(lengths[59], 1);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut model = Huffman::new();
+        assert!(model.decode(&[1, 2, 3]).is_err());
+        let encoded = model.encode(&[5, 5, 2, 5]).unwrap();
+        assert!(model.decode(&encoded[..20]).is_err());
+        assert!(model.decode(&encoded[..22]).is_err());
+        let mut invalid = encoded.clone();
+        invalid[16] = 0;
+        assert!(matches!(
+            model.decode(&invalid),
+            Err(CodingError::InvalidTable)
+        ));
+        decode::<Huffman>(&encoded, &[5, 5, 2, 5]);
+    }
+}
@@ -1,7 +1,74 @@
+//! This crate implements coding algorithms to be used during compression.
+//!
+//! # Introduction
+//! The coding algorithms implemented in this crate save symbols as compact as possible.
+//! The symbols are encoded into bytes by the `encode` operation and decoded again
+//! by the `decode` operation defined by the `Coding` trait.
+use std::fmt;
+use std::{error::Error, fmt::Display};
+
+mod bits;
+mod huffman;
+
+pub use bits::{BitReader, BitWriter};
+pub use huffman::{Huffman, MAX_CODE_LENGTH};
+
+/// Trait for coding symbols into bytes
+pub trait Coding {
+    fn encode(&mut self, symbols: &[u32]) -> Result<Vec<u8>, CodingError>;
+    fn decode(&mut self, source: &[u8]) -> Result<Vec<u32>, CodingError>;
+}
+
+/// An enum representing possible errors during coding
+#[derive(Debug)]
+pub enum CodingError {
+    /// Buffer ends in the middle of a symbol
+    TruncatedBuffer,
+    /// Code table is not valid
+    InvalidTable,
+    /// Code is not part of the code table
+    InvalidCode,
+}
+
+impl Error for CodingError {
+    fn description(&self) -> &str {
+        match *self {
+            CodingError::TruncatedBuffer => "Truncated buffer",
+            CodingError::InvalidTable => "Invalid table",
+            CodingError::InvalidCode => "Invalid code",
+        }
+    }
+}
+
+impl Display for CodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            CodingError::TruncatedBuffer => write!(f, "Buffer ends in the middle of a symbol"),
+            CodingError::InvalidTable => write!(f, "Code table is not valid"),
+            CodingError::InvalidCode => write!(f, "Code is not part of the code table"),
+        }
+    }
+}
+
 #[cfg(test)]
-mod tests {
-    #[test]
-    fn it_works() {
-        assert_eq!(2 + 2, 4);
+#[allow(dead_code)]
+pub mod tests {
+    //! # Tests
+    //! This module defines helper functions for testing coding algorithms.
+    use crate::Coding;
+
+    /// Helper function for testing decoding
+    pub fn decode<M: Coding + Default>(input: &[u8], expected: &[u32]) {
+        let mut model: M = Default::default();
+        let result = model.decode(input).unwrap();
+        assert_eq!(result, expected)
+    }
+
+    /// Helper function for testing coding roundtrips
+    pub fn roundtrip<M: Coding + Default>(input: &[u32]) {
+        let mut model: M = Default::default();
+        let encoded = model.encode(input).unwrap();
+        let result = model.decode(&encoded).unwrap();
+        assert_eq!(result, input)
     }
 }
@@ -0,0 +1,28 @@
+//! # rscompress-testing
+//!
+//! `rscompress_testing` implements the test helpers shared by the rscompress libraries.
+//! It is only used as a dev-dependency.
+use proptest::test_runner::{Config, RngSeed};
+use std::env;
+
+/// Default seed for the generation of random test data
+const SEED: u64 = 0x5EED_C0DE;
+
+/// Seed for the generation of random test data
+///
+/// The seed can be changed with the `RSCOMPRESS_SEED` environment variable
+/// to explore other inputs. Failing seeds are printed for reproduction.
+pub fn seed() -> u64 {
+    env::var("RSCOMPRESS_SEED")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(SEED)
+}
+
+/// Configuration of the property-based tests with a reproducible [`seed`]
+pub fn config() -> Config {
+    Config {
+        rng_seed: RngSeed::Fixed(seed()),
+        ..Config::default()
+    }
+}
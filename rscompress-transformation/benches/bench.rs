@@ -1,6 +1,9 @@
 use criterion::{criterion_group, criterion_main};
 use criterion::{BenchmarkId, Criterion, Throughput};
-use rscompress_transformation::{BurrowWheeler, MoveToFront, RunLength, Transform};
+use rscompress_transformation::{
+    BitShuffle, BurrowWheeler, ByteShuffle, Chain, Lz4Frame, Lz77, MoveToFront, PackBits,
+    RunLength, ThresholdRunLength, Transform, VarintRunLength, ZeroRunLength,
+};
 
 const MIN_DATA_SIZE: usize = 1_000;
 const FACTORS: [usize; 5] = [1, 5, 10, 50, 100];
@@ -10,8 +13,9 @@ fn criterion_roundtrip(c: &mut Criterion) {
     let source = include_bytes!("../../testdata/enwik7.raw");
     for factor in FACTORS.iter() {
         let size = factor * MIN_DATA_SIZE;
-        let data: Vec<u8> = source.iter().take(size).map(|x| *x).collect();
+        let data: Vec<u8> = source.iter().take(size).copied().collect();
         let mut tmp: Vec<u8> = Vec::with_capacity(size);
+        let mut out: Vec<u8> = Vec::with_capacity(size);
         group.throughput(Throughput::Bytes(size as u64));
 
         // Testing Run Length Transformation
@@ -20,12 +24,63 @@ fn criterion_roundtrip(c: &mut Criterion) {
             BenchmarkId::new("Run-Length (T)", size),
             data.as_slice(),
             |b, s| {
-                b.iter(|| tmp = model.transform(s).unwrap());
+                b.iter(|| model.transform_into(s, &mut tmp).unwrap());
             },
         );
         group.bench_with_input(
             BenchmarkId::new("Run-Length (R)", size),
             tmp.as_slice(),
+            |b, s| {
+                b.iter(|| model.reverse_into(s, &mut out).unwrap());
+            },
+        );
+
+        // Testing PackBits Transformation
+        let mut model = PackBits::new();
+        group.bench_with_input(
+            BenchmarkId::new("PackBits (T)", size),
+            data.as_slice(),
+            |b, s| {
+                b.iter(|| tmp = model.transform(s).unwrap());
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("PackBits (R)", size),
+            tmp.as_slice(),
+            |b, s| {
+                b.iter(|| model.reverse(s).unwrap());
+            },
+        );
+
+        // Testing Varint Run Length Transformation
+        let mut model = VarintRunLength::new();
+        group.bench_with_input(
+            BenchmarkId::new("Varint-Run-Length (T)", size),
+            data.as_slice(),
+            |b, s| {
+                b.iter(|| tmp = model.transform(s).unwrap());
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("Varint-Run-Length (R)", size),
+            tmp.as_slice(),
+            |b, s| {
+                b.iter(|| model.reverse(s).unwrap());
+            },
+        );
+
+        // Testing Threshold Run Length Transformation
+        let mut model = ThresholdRunLength::new();
+        group.bench_with_input(
+            BenchmarkId::new("Threshold-Run-Length (T)", size),
+            data.as_slice(),
+            |b, s| {
+                b.iter(|| tmp = model.transform(s).unwrap());
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("Threshold-Run-Length (R)", size),
+            tmp.as_slice(),
             |b, s| {
                 b.iter(|| model.reverse(s).unwrap());
             },
@@ -37,36 +92,174 @@ fn criterion_roundtrip(c: &mut Criterion) {
             BenchmarkId::new("Move-To-Front (T)", size),
             data.as_slice(),
             |b, s| {
-                b.iter(|| tmp = model.transform(s).unwrap());
+                b.iter(|| model.transform_into(s, &mut tmp).unwrap());
             },
         );
         group.bench_with_input(
             BenchmarkId::new("Move-To-Front (R)", size),
             tmp.as_slice(),
+            |b, s| {
+                b.iter(|| model.reverse_into(s, &mut out).unwrap());
+            },
+        );
+
+        // Testing Zero-Run Transformation
+        let mut model = ZeroRunLength::new();
+        group.bench_with_input(
+            BenchmarkId::new("Zero-Run (T)", size),
+            data.as_slice(),
+            |b, s| {
+                b.iter(|| tmp = model.transform(s).unwrap());
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("Zero-Run (R)", size),
+            tmp.as_slice(),
+            |b, s| {
+                b.iter(|| model.reverse(s).unwrap());
+            },
+        );
+
+        // Testing LZ77 Transformation
+        let mut model = Lz77::new();
+        group.bench_with_input(
+            BenchmarkId::new("LZ77 (T)", size),
+            data.as_slice(),
+            |b, s| {
+                b.iter(|| tmp = model.transform(s).unwrap());
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("LZ77 (R)", size),
+            tmp.as_slice(),
             |b, s| {
                 b.iter(|| model.reverse(s).unwrap());
             },
         );
 
+        // Testing LZ4 Frame Transformation
+        let mut model = Lz4Frame::new();
+        group.bench_with_input(
+            BenchmarkId::new("LZ4 (T)", size),
+            data.as_slice(),
+            |b, s| {
+                b.iter(|| tmp = model.transform(s).unwrap());
+            },
+        );
+        group.bench_with_input(BenchmarkId::new("LZ4 (R)", size), tmp.as_slice(), |b, s| {
+            b.iter(|| model.reverse(s).unwrap());
+        });
+
         // Testing Burrow Wheeler Transformation
         let mut model = BurrowWheeler::new();
         group.bench_with_input(
             BenchmarkId::new("Burow-Wheeler (T)", size),
             data.as_slice(),
             |b, s| {
-                b.iter(|| tmp = model.transform(s).unwrap());
+                b.iter(|| model.transform_into(s, &mut tmp).unwrap());
             },
         );
         group.bench_with_input(
             BenchmarkId::new("Burow-Wheeler (R)", size),
             tmp.as_slice(),
             |b, s| {
-                b.iter(|| model.reverse(s).unwrap());
+                b.iter(|| model.reverse_into(s, &mut out).unwrap());
             },
         );
     }
     group.finish();
 }
 
-criterion_group!(transform, criterion_roundtrip);
+fn criterion_numerical(c: &mut Criterion) {
+    let mut group = c.benchmark_group("numerical");
+    let sources: [(&str, &[u8]); 2] = [
+        ("f32", include_bytes!("../../testdata/f32_256normal7.raw")),
+        ("u32", include_bytes!("../../testdata/u32_uniform7.raw")),
+    ];
+    for (dtype, source) in sources.iter() {
+        for factor in FACTORS.iter() {
+            let size = factor * MIN_DATA_SIZE * 4;
+            let data: Vec<u8> = source.iter().take(size).copied().collect();
+            let mut tmp: Vec<u8> = Vec::with_capacity(size);
+            group.throughput(Throughput::Bytes(size as u64));
+
+            // Testing Byte Shuffle Transformation
+            let mut model = ByteShuffle::new(4);
+            group.bench_with_input(
+                BenchmarkId::new(format!("Byte-Shuffle {} (T)", dtype), size),
+                data.as_slice(),
+                |b, s| {
+                    b.iter(|| tmp = model.transform(s).unwrap());
+                },
+            );
+            group.bench_with_input(
+                BenchmarkId::new(format!("Byte-Shuffle {} (R)", dtype), size),
+                tmp.as_slice(),
+                |b, s| {
+                    b.iter(|| model.reverse(s).unwrap());
+                },
+            );
+
+            // Testing Bit Shuffle Transformation
+            let mut model = BitShuffle::new(4);
+            group.bench_with_input(
+                BenchmarkId::new(format!("Bit-Shuffle {} (T)", dtype), size),
+                data.as_slice(),
+                |b, s| {
+                    b.iter(|| tmp = model.transform(s).unwrap());
+                },
+            );
+            group.bench_with_input(
+                BenchmarkId::new(format!("Bit-Shuffle {} (R)", dtype), size),
+                tmp.as_slice(),
+                |b, s| {
+                    b.iter(|| model.reverse(s).unwrap());
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+/// Report the compression ratio of the run-length and dictionary transformations on the textual data
+fn criterion_ratio(_c: &mut Criterion) {
+    let source = include_bytes!("../../testdata/enwik7.raw");
+    let mut models: Vec<(&str, Box<dyn Transform>)> = vec![
+        ("Run-Length", Box::new(RunLength::new())),
+        ("PackBits", Box::new(PackBits::new())),
+        ("Varint-Run-Length", Box::new(VarintRunLength::new())),
+        ("Threshold-Run-Length", Box::new(ThresholdRunLength::new())),
+        ("LZ77", Box::new(Lz77::new())),
+        (
+            "LZ77 (greedy)",
+            Box::new(Lz77::with_window_and_lazy(32_768, false)),
+        ),
+        ("LZ4", Box::new(Lz4Frame::new())),
+        (
+            "BWT+MTF+Zero-Run",
+            Box::new(Chain::with_stages(vec![
+                Box::new(BurrowWheeler::new()),
+                Box::new(MoveToFront::new()),
+                Box::new(ZeroRunLength::new()),
+            ])),
+        ),
+    ];
+    for (name, model) in models.iter_mut() {
+        let transformed = model.transform(source).unwrap();
+        println!(
+            "ratio/{:<25} {:>10} -> {:>10} bytes ({:.4})",
+            name,
+            source.len(),
+            transformed.len(),
+            source.len() as f64 / transformed.len() as f64
+        );
+    }
+}
+
+criterion_group!(
+    transform,
+    criterion_ratio,
+    criterion_roundtrip,
+    criterion_numerical
+);
 criterion_main!(transform);
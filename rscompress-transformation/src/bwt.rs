@@ -1,3 +1,4 @@
+use crate::varint::{push_varint, read_varint};
 use crate::{Transform, TransformError};
 use log::debug;
 use std::collections::HashMap;
@@ -116,27 +117,56 @@ impl Default for BurrowWheeler {
 impl Transform for BurrowWheeler {
     /// Transformation of the initial source data
     fn transform(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let mut result: Vec<u8> = Vec::with_capacity(source.len());
+        self.transform_into(source, &mut result)?;
+        Ok(result)
+    }
+    /// Reversing the initial transformation
+    fn reverse(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let mut result: Vec<u8> = Vec::with_capacity(self.size);
+        self.reverse_into(source, &mut result)?;
+        Ok(result)
+    }
+    /// Transformation of the initial source data into the given buffer
+    fn transform_into(
+        &mut self,
+        source: &[u8],
+        result: &mut Vec<u8>,
+    ) -> Result<(), TransformError> {
         self.size = source.len();
+        result.clear();
         if source.is_empty() {
-            return Err(TransformError::EmptyBufferError);
+            self.ix = None;
+            return Ok(());
         }
         debug!("Input {:?}", source);
-        let (_, mut sarr) = SuffixArray::new(source).into_parts();
+        let (_, sarr) = SuffixArray::new(source).into_parts();
         debug!("SARR {:?}", sarr);
         self.ix = sarr.iter().position(|&x| x == 0);
         let ix = self.ix.ok_or(TransformError::MissingIndex)?;
-        sarr[ix] = sarr.len() as u32 + 1;
-        let mut last_column: Vec<_> = sarr
-            .iter()
-            .map(|&x| *source.get((x - 1) as usize).unwrap_or(&0u8))
-            .collect();
-        last_column.remove(ix);
-        Ok(last_column)
+        result.reserve(source.len());
+        result.extend(
+            sarr.iter()
+                .enumerate()
+                .filter(|&(i, _)| i != ix)
+                .map(|(_, &x)| source[(x - 1) as usize]),
+        );
+        Ok(())
     }
-    /// Reversing the initial transformation
-    fn reverse(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+    /// Reversing the initial transformation into the given buffer
+    fn reverse_into(&mut self, source: &[u8], result: &mut Vec<u8>) -> Result<(), TransformError> {
         debug!("{:?}", self);
-        let bix = self.ix.ok_or(TransformError::MissingIndex)?;
+        if source.len() != self.size {
+            return Err(TransformError::SizeMismatch(self.size, source.len()));
+        }
+        if source.is_empty() {
+            result.clear();
+            return Ok(());
+        }
+        let bix = self
+            .ix
+            .filter(|ix| (1..=self.size).contains(ix))
+            .ok_or(TransformError::MissingIndex)?;
 
         // generate sorted vector
         let mut sorted = source.to_vec();
@@ -152,7 +182,8 @@ impl Transform for BurrowWheeler {
         debug!("Sorted: {:?}", sorted);
         debug!("Counts: {:?}", counts);
         debug!("Self: {:?}", self);
-        let mut result: Vec<u8> = vec![0u8; self.size];
+        result.clear();
+        result.resize(self.size, 0);
         let mut pos = bix - 1;
         for r in result.iter_mut() {
             let reversed = sorted[pos];
@@ -163,7 +194,29 @@ impl Transform for BurrowWheeler {
             pos = ix - (ix != 0 && ix < bix) as usize;
             debug!("{:?}", r);
         }
-        Ok(result)
+        Ok(())
+    }
+
+    /// The index and the size are saved as variable-length integers,
+    /// the index shifted by one such that zero represents a missing index.
+    fn export_metadata(&self) -> Vec<u8> {
+        let mut result: Vec<u8> = Vec::new();
+        push_varint(&mut result, self.ix.map_or(0, |ix| ix + 1));
+        push_varint(&mut result, self.size);
+        result
+    }
+    fn import_metadata(&mut self, metadata: &[u8]) -> Result<(), TransformError> {
+        let mut i = 0;
+        let mut read =
+            || read_varint(metadata, &mut i).map_err(|_| TransformError::InvalidMetadata);
+        let (ix, size) = (read()?, read()?);
+        // a present index lies in `1..=size`, i.e. is saved as `2..=size + 1`
+        if i != metadata.len() || ix == 1 || ix > size.saturating_add(1) {
+            return Err(TransformError::InvalidMetadata);
+        }
+        self.ix = ix.checked_sub(1);
+        self.size = size;
+        Ok(())
     }
 }
 
@@ -206,7 +259,10 @@ fn get_counts(sorted: &[u8]) -> Result<Vec<usize>, TransformError> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tests::{random_roundtrip, roundtrip, transform};
+    use crate::tests::{
+        metadata_roundtrip_with, random_roundtrip, roundtrip, roundtrip_into, short_roundtrip_with,
+        transform,
+    };
 
     #[test]
     fn test_counts() {
@@ -245,6 +301,53 @@ mod tests {
         roundtrip::<BurrowWheeler>("apple".as_bytes());
         roundtrip::<BurrowWheeler>("banana".as_bytes());
     }
+    #[test]
+    fn test_roundtrip_into() {
+        roundtrip_into::<BurrowWheeler>("bananabanana".as_bytes());
+        roundtrip_into::<BurrowWheeler>(&[0; 100]);
+    }
+
+    #[test]
+    fn test_metadata() {
+        let mut model = BurrowWheeler::new();
+        assert_eq!(model.export_metadata(), [0, 0]);
+        model.transform("bananabanana".as_bytes()).unwrap();
+        let metadata = model.export_metadata();
+        let mut other = BurrowWheeler::new();
+        other.import_metadata(&metadata).unwrap();
+        assert_eq!(other.ix, model.ix);
+        assert_eq!(other.size, 12);
+        assert!(other.import_metadata(&[1]).is_err());
+        assert!(other.import_metadata(&[5, 2]).is_err());
+        assert!(other.import_metadata(&[1, 2, 3]).is_err());
+        assert!(other.import_metadata(&[1, 5]).is_err());
+        assert!(other.import_metadata(&[7, 5]).is_err());
+        other.import_metadata(&[6, 5]).unwrap();
+        assert_eq!(other.ix, Some(5));
+        metadata_roundtrip_with(BurrowWheeler::new, &["compression".as_bytes(), &[0; 100]]);
+    }
+
+    #[test]
+    fn test_size_mismatch() {
+        let mut model = BurrowWheeler::new();
+        model.import_metadata(&[3, 100]).unwrap();
+        assert!(matches!(
+            model.reverse("apple".as_bytes()),
+            Err(TransformError::SizeMismatch(100, 5))
+        ));
+        let mut model = BurrowWheeler::with_ix_and_size(0, 5);
+        assert!(matches!(
+            model.reverse("apple".as_bytes()),
+            Err(TransformError::MissingIndex)
+        ));
+    }
+
+    #[test]
+    fn test_short_roundtrip() {
+        short_roundtrip_with(BurrowWheeler::new);
+        transform::<BurrowWheeler>(&[], &[]);
+    }
+
     #[test]
     fn test_random_roundtrip() {
         random_roundtrip::<BurrowWheeler>(100, 10_000);
@@ -0,0 +1,266 @@
+//! Chain of Transformations
+//!
+//! Combination of several transformations into a single one, e.g. the
+//! Burrow-Wheeler, Move-To-Front and Run-Length pipeline of [bzip2](https://en.wikipedia.org/wiki/Bzip2).
+use crate::varint::{push_varint, read_varint};
+use crate::{DataType, Transform, TransformError};
+use log::debug;
+use std::fmt;
+
+/// Chain struct to save the transformations in the order of application
+///
+/// The transformation applies all stages from first to last, the reverse applies
+/// them from last to first. Errors of a stage are wrapped into
+/// [`TransformError::StageFailed`] with the position of the failing stage in the chain.
+/// An empty chain returns the data unchanged.
+/// A chain accepts an element type, if every stage accepts it.
+/// The metadata of a chain is the metadata of all stages, each prefixed
+/// by its length as variable-length integer.
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress_transformation::{BurrowWheeler, Chain, MoveToFront, RunLength, Transform};
+/// let mut model = Chain::new();
+/// model.push(BurrowWheeler::new());
+/// model.push(MoveToFront::new());
+/// model.push(RunLength::new());
+/// let transformed = model.transform("bananabanana".as_bytes()).unwrap();
+/// assert_eq!(model.reverse(&transformed).unwrap(), "bananabanana".as_bytes());
+/// ```
+pub struct Chain {
+    stages: Vec<Box<dyn Transform>>,
+}
+
+impl Chain {
+    pub fn new() -> Self {
+        Self::with_stages(Vec::new())
+    }
+    /// Generate chain of the given transformations
+    pub fn with_stages(stages: Vec<Box<dyn Transform>>) -> Self {
+        Chain { stages }
+    }
+    /// Append a transformation at the end of the chain
+    pub fn push<T: Transform + 'static>(&mut self, stage: T) {
+        self.stages.push(Box::new(stage))
+    }
+    /// Number of stages in the chain
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+    /// Check if the chain has no stages
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+}
+
+impl Default for Chain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Chain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Chain")
+            .field("stages", &self.stages.len())
+            .finish()
+    }
+}
+
+fn stage_failed(stage: usize) -> impl Fn(TransformError) -> TransformError {
+    move |e| TransformError::StageFailed(stage, Box::new(e))
+}
+
+/// Implementation of the Transform trait for Chain
+impl Transform for Chain {
+    fn transform(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let mut result = source.to_vec();
+        for (i, stage) in self.stages.iter_mut().enumerate() {
+            result = stage.transform(&result).map_err(stage_failed(i))?;
+            debug!("Stage {} transformed into {} bytes", i, result.len());
+        }
+        Ok(result)
+    }
+
+    fn reverse(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let mut result = source.to_vec();
+        for (i, stage) in self.stages.iter_mut().enumerate().rev() {
+            result = stage.reverse(&result).map_err(stage_failed(i))?;
+        }
+        Ok(result)
+    }
+
+    fn accepts(&self, dtype: DataType) -> bool {
+        self.stages.iter().all(|stage| stage.accepts(dtype))
+    }
+
+    fn export_metadata(&self) -> Vec<u8> {
+        let mut result: Vec<u8> = Vec::new();
+        for stage in self.stages.iter() {
+            let metadata = stage.export_metadata();
+            push_varint(&mut result, metadata.len());
+            result.extend_from_slice(&metadata);
+        }
+        result
+    }
+    fn import_metadata(&mut self, metadata: &[u8]) -> Result<(), TransformError> {
+        let mut i = 0;
+        for (k, stage) in self.stages.iter_mut().enumerate() {
+            let len = read_varint(metadata, &mut i).map_err(|_| TransformError::InvalidMetadata)?;
+            let end = i.checked_add(len).ok_or(TransformError::InvalidMetadata)?;
+            let part = metadata
+                .get(i..end)
+                .ok_or(TransformError::InvalidMetadata)?;
+            stage.import_metadata(part).map_err(stage_failed(k))?;
+            i = end;
+        }
+        if i != metadata.len() {
+            return Err(TransformError::InvalidMetadata);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{
+        metadata_roundtrip_with, random_roundtrip_with, roundtrip, roundtrip_with,
+        short_roundtrip_with, transform_with,
+    };
+    use crate::{
+        Array, BurrowWheeler, ByteShuffle, Endianness, Lz4Block, MoveToFront, PredictionResidual,
+        Predictor, RunLength, Shape, ZeroRunLength,
+    };
+    use rscompress_types::Layout;
+
+    fn bzip2() -> Chain {
+        Chain::with_stages(vec![
+            Box::new(BurrowWheeler::new()),
+            Box::new(MoveToFront::new()),
+            Box::new(ZeroRunLength::new()),
+        ])
+    }
+
+    #[test]
+    fn test_empty_chain() {
+        let mut model = Chain::new();
+        assert!(model.is_empty());
+        transform_with(&mut model, &[1, 2, 3], &[1, 2, 3]);
+        roundtrip::<Chain>("compression".as_bytes());
+    }
+
+    #[test]
+    fn test_stages_in_order() {
+        let input = "bananabanana".as_bytes();
+        let mut expected = BurrowWheeler::new().transform(input).unwrap();
+        expected = MoveToFront::new().transform(&expected).unwrap();
+        expected = ZeroRunLength::new().transform(&expected).unwrap();
+        let mut model = bzip2();
+        assert_eq!(model.len(), 3);
+        transform_with(&mut model, input, &expected);
+    }
+
+    #[test]
+    fn test_failing_stage() {
+        let mut model = Chain::new();
+        model.push(MoveToFront::new());
+        model.push(Lz4Block::new());
+        let error = model.reverse(&[0x10, 7, 2, 0, 0]).unwrap_err();
+        assert!(matches!(
+            error,
+            TransformError::StageFailed(1, ref e) if matches!(**e, TransformError::InvalidDistance(2))
+        ));
+        let mut model = Chain::new();
+        model.push(PredictionResidual::<u8>::new(
+            Predictor::Plane,
+            Shape::new(2, 2, 1),
+        ));
+        model.push(MoveToFront::new());
+        let error = model.transform(&[1, 2, 3]).unwrap_err();
+        assert!(matches!(error, TransformError::StageFailed(0, _)));
+    }
+
+    #[test]
+    fn test_metadata() {
+        let chunks: [&[u8]; 3] = [
+            "banana".as_bytes(),
+            "bandana".as_bytes(),
+            "ananas".as_bytes(),
+        ];
+        metadata_roundtrip_with(bzip2, &chunks);
+        let mut model = bzip2();
+        model.transform(chunks[0]).unwrap();
+        let metadata = model.export_metadata();
+        // index and size of the Burrow-Wheeler, table of the Move-To-Front, no zero-run metadata
+        assert_eq!((metadata[0], metadata[2]), (2, 6));
+        assert_eq!(metadata[3..5], [0x81, 0x02]);
+        assert_eq!(metadata[metadata.len() - 1], 0);
+        assert!(model.import_metadata(&metadata[..10]).is_err());
+        assert!(model.import_metadata(&[0, 0, 0, 0]).is_err());
+        assert!(matches!(
+            model.import_metadata(&[0, 0, 0]),
+            Err(TransformError::StageFailed(0, _))
+        ));
+    }
+
+    #[test]
+    fn test_typed_stages() {
+        let mut model = Chain::new();
+        model.push(PredictionResidual::<u16>::new(
+            Predictor::Lorenzo,
+            Shape::new(3, 2, 1),
+        ));
+        model.push(ByteShuffle::new(2));
+        assert!(model.accepts(DataType::U16));
+        assert!(!model.accepts(DataType::I16));
+        assert!(bzip2().accepts(DataType::F64));
+
+        let data = [1u16, 2, 3, 260, 5, 6];
+        let little = Array::from_slice(&data, &[3, 2]).unwrap();
+        let expected = model.transform(little.as_bytes()).unwrap();
+        let big = little.to_endianness(Endianness::Big);
+        assert_eq!(model.transform_array(&big).unwrap(), expected);
+        let transposed = Array::with_layout(
+            Layout::with_endianness_and_strides(
+                DataType::U16,
+                Endianness::Little,
+                &[2, 3],
+                &[3, 1],
+            ),
+            little.as_bytes().to_vec(),
+        )
+        .unwrap();
+        let contiguous = transposed.contiguous();
+        assert_eq!(
+            model.transform_array(&transposed).unwrap(),
+            model.transform(contiguous.as_bytes()).unwrap()
+        );
+        let signed = Array::from_slice(&[1i16, 2, 3, 4, 5, 6], &[3, 2]).unwrap();
+        assert!(matches!(
+            model.transform_array(&signed),
+            Err(TransformError::UnsupportedType(DataType::I16))
+        ));
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        roundtrip_with(&mut bzip2(), "compressioncompressioncompression".as_bytes());
+        let mut model = Chain::new();
+        model.push(RunLength::new());
+        model.push(Lz4Block::new());
+        roundtrip_with(&mut model, &[0; 1_000]);
+    }
+
+    #[test]
+    fn test_short_roundtrip() {
+        short_roundtrip_with(bzip2);
+        short_roundtrip_with(Chain::new);
+    }
+
+    #[test]
+    fn test_random_roundtrip() {
+        random_roundtrip_with(bzip2, 10, 10_000);
+    }
+}
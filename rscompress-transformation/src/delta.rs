@@ -0,0 +1,182 @@
+//! Delta Transformation
+//!
+//! Implementation of the delta encoding as
+//! described [here](https://en.wikipedia.org/wiki/Delta_encoding).
+use crate::element::{from_elements, to_elements, Integer};
+use crate::{DataType, Transform, TransformError};
+use log::debug;
+use std::marker::PhantomData;
+
+/// Delta struct to save the order of the differences and the stride between elements
+///
+/// Each element is replaced by the difference to the element `stride` positions before.
+/// The first `stride` elements are kept as they are.
+/// Higher orders apply the differences repeatedly, e.g. the second order removes
+/// linear trends and results in constant values for quadratic sequences.
+/// The stride is useful for interleaved or multi-dimensional data.
+/// Using the length of a row as stride calculates the differences between rows.
+///
+/// The arithmetic is wrapping, such that every difference is representable
+/// by the element type `T`. The elements are read from bytes in little-endian order
+/// and trailing bytes which do not form a complete element are kept as they are.
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress_transformation::{Delta, Transform};
+/// let model = Delta::<u32>::with_order_and_stride(2, 1);
+/// assert_eq!(model.encode(&[1, 4, 9, 16, 25]), [1, 2, 2, 2, 2]);
+///
+/// let mut model = Delta::<u16>::new();
+/// let transformed = model.transform(&[1, 0, 3, 0, 6, 0]).unwrap();
+/// assert_eq!(transformed, [1, 0, 2, 0, 3, 0]);
+/// ```
+#[derive(Debug)]
+pub struct Delta<T: Integer> {
+    order: usize,
+    stride: usize,
+    element: PhantomData<T>,
+}
+
+impl<T: Integer> Delta<T> {
+    pub fn new() -> Self {
+        Self::with_order_and_stride(1, 1)
+    }
+    /// Generate delta transformation of the given order with a stride (at least 1)
+    pub fn with_order_and_stride(order: usize, stride: usize) -> Self {
+        Delta {
+            order,
+            stride: stride.max(1),
+            element: PhantomData,
+        }
+    }
+    /// Calculate the differences of typed data
+    pub fn encode(&self, data: &[T]) -> Vec<T> {
+        let mut result = data.to_vec();
+        for _ in 0..self.order {
+            for i in (self.stride..result.len()).rev() {
+                result[i] = result[i].wrapping_sub(result[i - self.stride]);
+            }
+        }
+        result
+    }
+    /// Reverse the differences of typed data
+    pub fn decode(&self, data: &[T]) -> Vec<T> {
+        let mut result = data.to_vec();
+        for _ in 0..self.order {
+            for i in self.stride..result.len() {
+                result[i] = result[i].wrapping_add(result[i - self.stride]);
+            }
+        }
+        result
+    }
+}
+
+impl<T: Integer> Default for Delta<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Implementation of the Transform trait for Delta
+impl<T: Integer> Transform for Delta<T> {
+    fn transform(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let (elements, trailing) = to_elements::<T>(source);
+        debug!("Delta of {} elements with {:?}", elements.len(), self);
+        Ok(from_elements(&self.encode(&elements), trailing))
+    }
+
+    fn reverse(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let (elements, trailing) = to_elements::<T>(source);
+        Ok(from_elements(&self.decode(&elements), trailing))
+    }
+
+    fn accepts(&self, dtype: DataType) -> bool {
+        dtype == T::DTYPE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{
+        random_roundtrip, random_roundtrip_with, roundtrip, short_roundtrip_with, transform,
+    };
+
+    #[test]
+    fn test_easy_encodes() {
+        let model = Delta::<i32>::new();
+        assert_eq!(model.encode(&[5, 7, 4, 4]), [5, 2, -3, 0]);
+        assert_eq!(model.decode(&[5, 2, -3, 0]), [5, 7, 4, 4]);
+        let model = Delta::<i8>::with_order_and_stride(2, 1);
+        assert_eq!(model.encode(&[0, 1, 4, 9, 16]), [0, 1, 2, 2, 2]);
+        let model = Delta::<u8>::with_order_and_stride(0, 1);
+        assert_eq!(model.encode(&[3, 1, 4]), [3, 1, 4]);
+    }
+
+    #[test]
+    fn test_wrapping_encodes() {
+        let model = Delta::<u8>::new();
+        assert_eq!(model.encode(&[255, 0, 255]), [255, 1, 255]);
+        assert_eq!(model.decode(&[255, 1, 255]), [255, 0, 255]);
+        let model = Delta::<i64>::new();
+        assert_eq!(model.encode(&[i64::MIN, i64::MAX]), [i64::MIN, -1]);
+    }
+
+    #[test]
+    fn test_stride_encodes() {
+        // interleaved data of two channels
+        let model = Delta::<u16>::with_order_and_stride(1, 2);
+        assert_eq!(
+            model.encode(&[10, 100, 11, 102, 12, 104]),
+            [10, 100, 1, 2, 1, 2]
+        );
+        // 2x3 grid, differences between rows
+        let model = Delta::<u64>::with_order_and_stride(1, 3);
+        assert_eq!(model.encode(&[1, 2, 3, 2, 3, 4]), [1, 2, 3, 1, 1, 1]);
+        assert_eq!(model.decode(&[1, 2, 3, 1, 1, 1]), [1, 2, 3, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_easy_transforms() {
+        transform::<Delta<u8>>(&[1, 2, 4, 8], &[1, 1, 2, 4]);
+        transform::<Delta<u16>>(&[1, 0, 3, 0, 6, 0, 9], &[1, 0, 2, 0, 3, 0, 9]);
+        transform::<Delta<i32>>(&[2, 0, 0, 0, 1, 0, 0, 0], &[2, 0, 0, 0, 255, 255, 255, 255]);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        roundtrip::<Delta<u32>>(&[8, 2, 2, 2, 24, 32, 32, 1, 24]);
+        roundtrip::<Delta<i64>>(&[1, 2, 3]);
+        roundtrip::<Delta<i16>>("compressioncode".as_bytes());
+    }
+
+    #[test]
+    fn test_short_roundtrip() {
+        short_roundtrip_with(Delta::<u8>::new);
+        short_roundtrip_with(Delta::<i16>::new);
+        short_roundtrip_with(|| Delta::<u32>::with_order_and_stride(2, 3));
+        transform::<Delta<u64>>(&[], &[]);
+    }
+
+    #[test]
+    fn test_random_roundtrip() {
+        random_roundtrip::<Delta<u8>>(10, 10_001);
+        random_roundtrip::<Delta<u16>>(10, 10_001);
+        random_roundtrip::<Delta<u32>>(10, 10_001);
+        random_roundtrip::<Delta<u64>>(10, 10_001);
+        random_roundtrip::<Delta<i8>>(10, 10_001);
+        random_roundtrip::<Delta<i16>>(10, 10_001);
+        random_roundtrip::<Delta<i32>>(10, 10_001);
+        random_roundtrip::<Delta<i64>>(10, 10_001);
+        random_roundtrip_with(|| Delta::<i32>::with_order_and_stride(2, 3), 10, 10_001);
+        random_roundtrip_with(|| Delta::<u16>::with_order_and_stride(3, 100), 10, 10_001);
+    }
+
+    #[test]
+    fn test_accepts() {
+        assert!(Delta::<i64>::new().accepts(DataType::I64));
+        assert!(!Delta::<i64>::new().accepts(DataType::U64));
+        assert!(!Delta::<i64>::new().accepts(DataType::F64));
+    }
+}
@@ -0,0 +1,73 @@
+//! Typed elements
+//!
+//! Helpers for transformations working on arrays of primitive numbers.
+//! The elements are read from and written to bytes in little-endian order.
+//! Trailing bytes, which do not form a complete element, are kept as they are.
+//! Typed arrays are normalized into these bytes by `Transform::transform_array`.
+use rscompress_types::{Endianness, Primitive};
+
+/// Trait for integers supporting wrapping arithmetic
+pub trait Integer: Primitive {
+    fn wrapping_add(self, other: Self) -> Self;
+    fn wrapping_sub(self, other: Self) -> Self;
+}
+
+macro_rules! impl_integer {
+    ($($t:ty),*) => {
+        $(
+            impl Integer for $t {
+                fn wrapping_add(self, other: Self) -> Self {
+                    <$t>::wrapping_add(self, other)
+                }
+                fn wrapping_sub(self, other: Self) -> Self {
+                    <$t>::wrapping_sub(self, other)
+                }
+            }
+        )*
+    };
+}
+
+impl_integer!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+/// Split bytes into complete elements and the trailing bytes
+pub(crate) fn to_elements<T: Primitive>(source: &[u8]) -> (Vec<T>, &[u8]) {
+    let size = T::DTYPE.size();
+    let complete = source.len() - source.len() % size;
+    let elements = source[..complete]
+        .chunks_exact(size)
+        .map(|bytes| T::from_bytes(bytes, Endianness::Little))
+        .collect();
+    (elements, &source[complete..])
+}
+
+/// Join elements and trailing bytes into bytes
+pub(crate) fn from_elements<T: Primitive>(elements: &[T], trailing: &[u8]) -> Vec<u8> {
+    let mut result: Vec<u8> = Vec::with_capacity(elements.len() * T::DTYPE.size() + trailing.len());
+    for e in elements.iter() {
+        e.write_bytes(Endianness::Little, &mut result);
+    }
+    result.extend_from_slice(trailing);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_elements() {
+        let (elements, trailing) = to_elements::<u16>(&[1, 0, 0, 1, 7]);
+        assert_eq!(elements, [1, 256]);
+        assert_eq!(trailing, [7]);
+        assert_eq!(from_elements(&elements, trailing), [1, 0, 0, 1, 7]);
+    }
+
+    #[test]
+    fn test_float_elements() {
+        let bytes = from_elements(&[-0.0f32, f32::NAN, 1.5], &[]);
+        let (elements, _) = to_elements::<f32>(&bytes);
+        assert_eq!(elements[0].to_bits(), (-0.0f32).to_bits());
+        assert_eq!(elements[1].to_bits(), f32::NAN.to_bits());
+        assert_eq!(elements[2], 1.5);
+    }
+}
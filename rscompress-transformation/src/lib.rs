@@ -7,17 +7,103 @@
 use std::fmt;
 use std::{error::Error, fmt::Display};
 mod bwt;
+mod chain;
+mod delta;
+mod element;
+mod lz4;
+mod lz77;
 mod movetofront;
+mod packbits;
+mod predictor;
+#[cfg(test)]
+mod properties;
 mod runlength;
+mod shuffle;
+mod thresholdrunlength;
+mod varint;
+mod varintrunlength;
+mod wfc;
+mod xor;
+mod zerorun;
 
 pub use bwt::BurrowWheeler;
-pub use movetofront::MoveToFront;
+pub use chain::Chain;
+pub use delta::Delta;
+pub use element::Integer;
+pub use lz4::{Lz4Block, Lz4BlockSize, Lz4Frame, LZ4_MAGIC};
+pub use lz77::{Lz77, Token, MAX_MATCH, MIN_MATCH};
+pub use movetofront::{Continuity, MoveToFront, Policy};
+pub use packbits::PackBits;
+pub use predictor::{Predictable, PredictionResidual, Predictor, Shape};
+pub use rscompress_types::{Array, DataType, Endianness, Float, Primitive};
 pub use runlength::RunLength;
+pub use shuffle::{BitShuffle, ByteShuffle};
+pub use thresholdrunlength::ThresholdRunLength;
+pub use varintrunlength::{VarintRunLength, MAX_VARINT_RUN};
+pub use wfc::WeightedFrequencyCount;
+pub use xor::{XorPredictor, XorResidual};
+pub use zerorun::{ZeroRunLength, MAX_ZERO_RUN, RUNA, RUNB, ZERO_RUN_ALPHABET_SIZE};
 
 /// Trait for calculating transformations on byte level
 pub trait Transform {
     fn transform(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError>;
     fn reverse(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError>;
+    /// Transformation writing into the given buffer
+    ///
+    /// The buffer is cleared before writing, but its allocation is reused where the
+    /// implementation supports it. The content of the buffer is unspecified on errors.
+    /// The default implementation replaces the buffer by the result of `transform`.
+    fn transform_into(
+        &mut self,
+        source: &[u8],
+        target: &mut Vec<u8>,
+    ) -> Result<(), TransformError> {
+        *target = self.transform(source)?;
+        Ok(())
+    }
+    /// Reverse transformation writing into the given buffer (see `transform_into`)
+    fn reverse_into(&mut self, source: &[u8], target: &mut Vec<u8>) -> Result<(), TransformError> {
+        *target = self.reverse(source)?;
+        Ok(())
+    }
+    /// Check if the transformation works on arrays of the element type
+    ///
+    /// Byte-level transformations accept every element type, which is the default.
+    /// Typed transformations accept only the elements they interpret.
+    fn accepts(&self, _dtype: DataType) -> bool {
+        true
+    }
+    /// Transform the elements of a typed array
+    ///
+    /// The elements are transformed as contiguous bytes in little-endian order.
+    /// Arrays of element types, which are not accepted, are rejected.
+    fn transform_array(&mut self, array: &Array) -> Result<Vec<u8>, TransformError> {
+        let dtype = array.layout().dtype;
+        if !self.accepts(dtype) {
+            return Err(TransformError::UnsupportedType(dtype));
+        }
+        let normalized = array.to_endianness(Endianness::Little);
+        let len = array.layout().len() * dtype.size();
+        self.transform(&normalized.as_bytes()[..len])
+    }
+    /// Export the side information needed to reverse the last transformation
+    ///
+    /// The metadata exported after `transform` contains everything a new model needs
+    /// to `reverse` the output of this call after importing it, e.g. the index of the
+    /// Burrow-Wheeler Transformation or the table of a Move-To-Front stream.
+    /// Containers and pipelines can therefore store the metadata next to the data.
+    /// Transformations without side information use the default implementation,
+    /// which exports no bytes and accepts only empty metadata.
+    fn export_metadata(&self) -> Vec<u8> {
+        Vec::new()
+    }
+    /// Import the side information exported by `export_metadata` before reversing
+    fn import_metadata(&mut self, metadata: &[u8]) -> Result<(), TransformError> {
+        if !metadata.is_empty() {
+            return Err(TransformError::InvalidMetadata);
+        }
+        Ok(())
+    }
 }
 
 /// An enum representing possible errors during transformation
@@ -33,6 +119,28 @@ pub enum TransformError {
     MissingMapping(u8),
     /// Missing count of symbol
     MissingCountMap(u8, usize),
+    /// Buffer ends in the middle of a symbol
+    TruncatedBuffer,
+    /// Symbol is outside of the alphabet
+    InvalidSymbol(u16),
+    /// Run length can not be represented
+    InvalidRunLength,
+    /// Number of elements does not match the shape (expected, actual)
+    ShapeMismatch(usize, usize),
+    /// Match references data before the start of the buffer
+    InvalidDistance(usize),
+    /// Header of the data is not valid or not supported
+    InvalidHeader,
+    /// Checksum does not match the data (expected, actual)
+    ChecksumMismatch(u32, u32),
+    /// Size of the decoded data does not match (expected, actual)
+    SizeMismatch(usize, usize),
+    /// Stage of a chain failed (position of the stage, error of the stage)
+    StageFailed(usize, Box<TransformError>),
+    /// Metadata can not be imported
+    InvalidMetadata,
+    /// Element type is not accepted by the transformation
+    UnsupportedType(DataType),
 }
 
 impl Error for TransformError {
@@ -43,6 +151,24 @@ impl Error for TransformError {
             TransformError::MissingIndex => "Missing index position",
             TransformError::MissingMapping(_val) => "No Mapping",
             TransformError::MissingCountMap(_, _) => "Can not find enough occurences of symbol",
+            TransformError::TruncatedBuffer => "Truncated buffer",
+            TransformError::InvalidSymbol(_val) => "Invalid symbol",
+            TransformError::InvalidRunLength => "Invalid run length",
+            TransformError::ShapeMismatch(_, _) => "Shape mismatch",
+            TransformError::InvalidDistance(_val) => "Invalid distance",
+            TransformError::InvalidHeader => "Invalid header",
+            TransformError::ChecksumMismatch(_, _) => "Checksum mismatch",
+            TransformError::SizeMismatch(_, _) => "Size mismatch",
+            TransformError::StageFailed(_, _) => "Stage failed",
+            TransformError::InvalidMetadata => "Invalid metadata",
+            TransformError::UnsupportedType(_) => "Unsupported type",
+        }
+    }
+
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TransformError::StageFailed(_, e) => Some(e.as_ref()),
+            _ => None,
         }
     }
 }
@@ -57,6 +183,33 @@ impl Display for TransformError {
             TransformError::MissingCountMap(sym, c) => {
                 write!(f, "Missing {:?}. occurence of symbol '{:?}'", c + 1, sym)
             }
+            TransformError::TruncatedBuffer => write!(f, "Buffer ends in the middle of a symbol"),
+            TransformError::InvalidSymbol(val) => write!(f, "Symbol [{:?}] is not valid", val),
+            TransformError::InvalidRunLength => write!(f, "Run length is too large"),
+            TransformError::ShapeMismatch(expected, actual) => {
+                write!(f, "Expected {:?} elements but got {:?}", expected, actual)
+            }
+            TransformError::InvalidDistance(val) => {
+                write!(f, "Distance [{:?}] exceeds the decoded data", val)
+            }
+            TransformError::InvalidHeader => write!(f, "Header is not valid or not supported"),
+            TransformError::ChecksumMismatch(expected, actual) => {
+                write!(
+                    f,
+                    "Expected checksum {:#010x} but got {:#010x}",
+                    expected, actual
+                )
+            }
+            TransformError::SizeMismatch(expected, actual) => {
+                write!(f, "Expected {:?} bytes but got {:?}", expected, actual)
+            }
+            TransformError::StageFailed(stage, ref e) => {
+                write!(f, "Stage {:?} failed: {}", stage, e)
+            }
+            TransformError::InvalidMetadata => write!(f, "Metadata can not be imported"),
+            TransformError::UnsupportedType(dtype) => {
+                write!(f, "Elements of {:?} are not supported", dtype)
+            }
         }
     }
 }
@@ -67,42 +220,114 @@ pub mod tests {
     //! # Tests
     //! This module defines helper functions for testing transformation algorithms.
     use crate::Transform;
-    use rand::{rngs::OsRng, RngCore};
+    use rand::{rngs::StdRng, RngCore, SeedableRng};
+    pub use rscompress_testing::seed;
 
     /// Helper function for testing one-way transformation
     pub fn transform<M: Transform + Default>(input: &[u8], expected: &[u8]) {
         let mut model: M = Default::default();
-        let result = model.transform(&input).unwrap();
+        transform_with(&mut model, input, expected)
+    }
+
+    /// Helper function for testing one-way transformation of a given model
+    pub fn transform_with<M: Transform>(model: &mut M, input: &[u8], expected: &[u8]) {
+        let result = model.transform(input).unwrap();
         assert_eq!(result, expected)
     }
 
     /// Helper function for testing reverse transformation
     pub fn reverse<M: Transform + Default>(input: &[u8], expected: &[u8]) {
         let mut model: M = Default::default();
-        let result = model.reverse(&input).unwrap();
+        let result = model.reverse(input).unwrap();
         assert_eq!(result, expected)
     }
 
     /// Helper function for testing transformation roundtrips
     pub fn roundtrip<M: Transform + Default>(input: &[u8]) {
-        println!("Input:       {:?}", input);
         let mut model: M = Default::default();
-        let tmp = model.transform(&input).unwrap();
+        roundtrip_with(&mut model, input)
+    }
+
+    /// Helper function for testing transformation roundtrips of a given model
+    pub fn roundtrip_with<M: Transform>(model: &mut M, input: &[u8]) {
+        println!("Input:       {:?}", input);
+        let tmp = model.transform(input).unwrap();
         println!("Transformed: {:?}", tmp);
         let result = model.reverse(&tmp).unwrap();
         println!("Reversed:    {:?}", result);
         assert_eq!(result, input)
     }
 
+    /// Helper function for testing transformations into reused buffers
+    ///
+    /// The results must be the same as the allocating transformations of a second model.
+    pub fn roundtrip_into<M: Transform + Default>(input: &[u8]) {
+        let (mut model, mut reference): (M, M) = (Default::default(), Default::default());
+        let mut tmp: Vec<u8> = vec![42; 3];
+        let mut result: Vec<u8> = Vec::with_capacity(2 * input.len() + 16);
+        result.extend_from_slice(&[1, 2, 3]);
+        let capacity = result.capacity();
+        model.transform_into(input, &mut tmp).unwrap();
+        assert_eq!(tmp, reference.transform(input).unwrap());
+        model.reverse_into(&tmp, &mut result).unwrap();
+        assert_eq!(result, reference.reverse(&tmp).unwrap());
+        assert_eq!(result, input);
+        assert_eq!(result.capacity(), capacity);
+    }
+
+    /// Helper function for testing the reversal of chunks by new models using the metadata
+    pub fn metadata_roundtrip_with<M: Transform, F: Fn() -> M>(model: F, chunks: &[&[u8]]) {
+        let mut forward = model();
+        for chunk in chunks.iter() {
+            let tmp = forward.transform(chunk).unwrap();
+            let metadata = forward.export_metadata();
+            let mut backward = model();
+            backward.import_metadata(&metadata).unwrap();
+            assert_eq!(backward.reverse(&tmp).unwrap(), *chunk);
+        }
+    }
+
+    /// Edge cases of bytes for the tests of short inputs
+    const EDGE_BYTES: [u8; 12] = [0, 1, 2, 3, 15, 16, 127, 128, 129, 253, 254, 255];
+
+    /// Helper function for testing roundtrips of inputs with length 0, 1 and 2
+    ///
+    /// All single bytes are tested, as well as all pairs of bytes containing an edge case.
+    pub fn short_roundtrip_with<M: Transform, F: Fn() -> M>(model: F) {
+        let mut inputs: Vec<Vec<u8>> = vec![Vec::new()];
+        for a in 0..=255u8 {
+            inputs.push(vec![a]);
+            for b in EDGE_BYTES.iter() {
+                inputs.push(vec![a, *b]);
+                inputs.push(vec![*b, a]);
+            }
+        }
+        for input in inputs.iter() {
+            let mut model = model();
+            let tmp = model.transform(input).unwrap();
+            let result = model.reverse(&tmp).unwrap();
+            assert_eq!(result, *input, "Transformed: {:?}", tmp);
+        }
+        assert!(model().reverse(&[]).unwrap().is_empty());
+    }
+
     /// Helper function for testing random transformation roundtrips
     pub fn random_roundtrip<M: Transform + Default>(trips: usize, size: usize) {
+        random_roundtrip_with(M::default, trips, size)
+    }
+
+    /// Helper function for testing random transformation roundtrips of generated models
+    pub fn random_roundtrip_with<M: Transform, F: Fn() -> M>(model: F, trips: usize, size: usize) {
+        let seed = seed();
+        let mut rng = StdRng::seed_from_u64(seed);
         for _ in 0..trips {
             let mut input = vec![0u8; size];
-            OsRng.fill_bytes(&mut input);
-            let mut model: M = Default::default();
+            rng.fill_bytes(&mut input);
+            let mut model = model();
             let tmp = model.transform(&input).unwrap();
             let result = model.reverse(&tmp).unwrap();
             if result != input {
+                println!("Seed:        {}", seed);
                 println!("Input:       {:?}", input);
                 println!("Transformed: {:?}", tmp);
                 println!("Reversed:    {:?}", result);
@@ -0,0 +1,644 @@
+//! LZ4 Transformation
+//!
+//! Implementation of the [LZ4 block format](https://github.com/lz4/lz4/blob/dev/doc/lz4_Block_format.md)
+//! and the [LZ4 frame format](https://github.com/lz4/lz4/blob/dev/doc/lz4_Frame_format.md).
+//! The output can be read by the reference implementation and vice versa.
+use crate::{Transform, TransformError};
+use log::debug;
+use rscompress_checksums::{Checksum, XXH32};
+
+/// Magic number at the start of every LZ4 frame
+pub const LZ4_MAGIC: u32 = 0x184D_2204;
+
+const MIN_MATCH: usize = 4;
+const LAST_LITERALS: usize = 5;
+const MF_LIMIT: usize = 12;
+const MAX_OFFSET: usize = 65_535;
+const HASH_BITS: u32 = 16;
+const NONE: usize = usize::MAX;
+const SKIPPABLE_MAGIC: u32 = 0x184D_2A50;
+const UNCOMPRESSED_FLAG: u32 = 1 << 31;
+
+fn hash(source: &[u8], pos: usize) -> usize {
+    let v = u32::from_le_bytes([
+        source[pos],
+        source[pos + 1],
+        source[pos + 2],
+        source[pos + 3],
+    ]);
+    (v.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+}
+
+fn push_length(result: &mut Vec<u8>, mut value: usize) {
+    while value >= 255 {
+        result.push(255);
+        value -= 255;
+    }
+    result.push(value as u8);
+}
+
+fn read_length(source: &[u8], i: &mut usize, base: usize) -> Result<usize, TransformError> {
+    let mut value = base;
+    if base == 15 {
+        loop {
+            let byte = *source.get(*i).ok_or(TransformError::TruncatedBuffer)?;
+            *i += 1;
+            value = value
+                .checked_add(byte as usize)
+                .ok_or(TransformError::InvalidRunLength)?;
+            if byte != 255 {
+                break;
+            }
+        }
+    }
+    Ok(value)
+}
+
+/// Write a sequence of literals followed by an optional match `(offset, length)`
+fn push_sequence(result: &mut Vec<u8>, literals: &[u8], matched: Option<(usize, usize)>) {
+    let lit = literals.len();
+    let ml = matched.map_or(0, |(_, length)| length - MIN_MATCH);
+    result.push((lit.min(15) as u8) << 4 | ml.min(15) as u8);
+    if lit >= 15 {
+        push_length(result, lit - 15);
+    }
+    result.extend_from_slice(literals);
+    if let Some((offset, _)) = matched {
+        result.extend_from_slice(&(offset as u16).to_le_bytes());
+        if ml >= 15 {
+            push_length(result, ml - 15);
+        }
+    }
+}
+
+/// Compress a single independent block
+fn compress_block(source: &[u8]) -> Vec<u8> {
+    let n = source.len();
+    let mut result: Vec<u8> = Vec::with_capacity(n + n / 255 + 16);
+    let mut anchor = 0;
+    if n > MF_LIMIT {
+        let mut table = vec![NONE; 1 << HASH_BITS];
+        let mut i = 0;
+        while i < n - MF_LIMIT {
+            let h = hash(source, i);
+            let candidate = table[h];
+            table[h] = i;
+            if candidate == NONE
+                || i - candidate > MAX_OFFSET
+                || source[candidate..candidate + MIN_MATCH] != source[i..i + MIN_MATCH]
+            {
+                i += 1;
+                continue;
+            }
+            let length = MIN_MATCH
+                + source[candidate + MIN_MATCH..]
+                    .iter()
+                    .zip(source[i + MIN_MATCH..n - LAST_LITERALS].iter())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+            push_sequence(
+                &mut result,
+                &source[anchor..i],
+                Some((i - candidate, length)),
+            );
+            i += length;
+            anchor = i;
+        }
+    }
+    push_sequence(&mut result, &source[anchor..], None);
+    result
+}
+
+/// Decompress a single block and append it to `result`
+///
+/// Matches may reference data of `result` starting at `history`.
+/// Blocks decompressing into more than `limit` bytes are rejected as soon as the limit is exceeded.
+fn decompress_block(
+    source: &[u8],
+    result: &mut Vec<u8>,
+    history: usize,
+    limit: usize,
+) -> Result<(), TransformError> {
+    let end = result.len().saturating_add(limit);
+    let mut i = 0;
+    loop {
+        let token = *source.get(i).ok_or(TransformError::TruncatedBuffer)?;
+        i += 1;
+        let lit = read_length(source, &mut i, (token >> 4) as usize)?;
+        if lit > end - result.len() {
+            return Err(TransformError::InvalidHeader);
+        }
+        let literals = source
+            .get(i..i.saturating_add(lit))
+            .ok_or(TransformError::TruncatedBuffer)?;
+        result.extend_from_slice(literals);
+        i += lit;
+        if i == source.len() {
+            return Ok(());
+        }
+        let offset = source
+            .get(i..i + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]) as usize)
+            .ok_or(TransformError::TruncatedBuffer)?;
+        i += 2;
+        if offset == 0 || offset > result.len() - history {
+            return Err(TransformError::InvalidDistance(offset));
+        }
+        let length = read_length(source, &mut i, (token & 15) as usize)?
+            .checked_add(MIN_MATCH)
+            .filter(|length| *length <= end - result.len())
+            .ok_or(TransformError::InvalidHeader)?;
+        let start = result.len() - offset;
+        // byte wise, because the match may overlap with itself
+        for k in 0..length {
+            result.push(result[start + k]);
+        }
+    }
+}
+
+/// LZ4 block struct for the raw block format without framing
+///
+/// The data is compressed as a single independent block. The block format
+/// does not save the size of the data, which is needed by other implementations to decompress it.
+/// Use [`Lz4Frame`] for data exchange with other tools.
+/// Empty data is compressed into a single token without literals
+/// and an empty buffer is reversed into empty data.
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress_transformation::{Lz4Block, Transform};
+/// let mut model = Lz4Block::new();
+/// let transformed = model.transform(&[1, 2, 3]).unwrap();
+/// assert_eq!(transformed, [0x30, 1, 2, 3]);
+/// ```
+#[derive(Debug)]
+pub struct Lz4Block {}
+
+impl Lz4Block {
+    pub fn new() -> Self {
+        Lz4Block {}
+    }
+}
+
+impl Default for Lz4Block {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Implementation of the Transform trait for LZ4 blocks
+impl Transform for Lz4Block {
+    fn transform(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let result = compress_block(source);
+        debug!(
+            "LZ4 block compressed {} bytes into {}",
+            source.len(),
+            result.len()
+        );
+        Ok(result)
+    }
+
+    fn reverse(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let mut result: Vec<u8> = Vec::with_capacity(source.len() * 2);
+        if source.is_empty() {
+            return Ok(result);
+        }
+        decompress_block(source, &mut result, 0, usize::MAX)?;
+        Ok(result)
+    }
+}
+
+/// Maximum size of the uncompressed blocks of a LZ4 frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lz4BlockSize {
+    Max64KB = 4,
+    Max256KB = 5,
+    Max1MB = 6,
+    Max4MB = 7,
+}
+
+impl Lz4BlockSize {
+    /// Size of the blocks in bytes
+    pub fn bytes(&self) -> usize {
+        1 << (8 + 2 * (*self as usize))
+    }
+    fn from_id(id: u8) -> Option<Self> {
+        match id {
+            4 => Some(Lz4BlockSize::Max64KB),
+            5 => Some(Lz4BlockSize::Max256KB),
+            6 => Some(Lz4BlockSize::Max1MB),
+            7 => Some(Lz4BlockSize::Max4MB),
+            _ => None,
+        }
+    }
+}
+
+/// LZ4 frame struct to save the block size and the optional fields of the frame
+///
+/// The data is split into independent blocks of at most `block_size` bytes.
+/// Blocks which can not be compressed are stored uncompressed.
+/// Block and content checksums are [XXH32](rscompress_checksums::XXH32) checksums.
+/// The content size is saved in the frame descriptor if `content_size` is set.
+///
+/// Reversing accepts any valid frame of the reference implementation including
+/// linked blocks, concatenated frames and skippable frames. Frames using
+/// a dictionary are not supported.
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress_transformation::{Lz4Frame, Transform};
+/// let mut model = Lz4Frame::new();
+/// let transformed = model.transform("compression".as_bytes()).unwrap();
+/// assert_eq!(transformed[..4], [0x04, 0x22, 0x4d, 0x18]);
+/// assert_eq!(model.reverse(&transformed).unwrap(), "compression".as_bytes());
+/// ```
+#[derive(Debug)]
+pub struct Lz4Frame {
+    block_size: Lz4BlockSize,
+    block_checksum: bool,
+    content_checksum: bool,
+    content_size: bool,
+}
+
+impl Lz4Frame {
+    /// Generate frames with the defaults of the reference implementation
+    /// i.e. blocks of 4MB and a content checksum
+    pub fn new() -> Self {
+        Self::with_options(Lz4BlockSize::Max4MB, false, true, false)
+    }
+    pub fn with_options(
+        block_size: Lz4BlockSize,
+        block_checksum: bool,
+        content_checksum: bool,
+        content_size: bool,
+    ) -> Self {
+        Lz4Frame {
+            block_size,
+            block_checksum,
+            content_checksum,
+            content_size,
+        }
+    }
+}
+
+impl Default for Lz4Frame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn xxh32(data: &[u8]) -> u32 {
+    let mut checksum = XXH32::new();
+    checksum.update(data);
+    checksum.checksum().unwrap_or_default()
+}
+
+fn read_u32(source: &[u8], i: &mut usize) -> Result<u32, TransformError> {
+    let bytes = source
+        .get(*i..*i + 4)
+        .ok_or(TransformError::TruncatedBuffer)?;
+    *i += 4;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn check(expected: u32, actual: u32) -> Result<(), TransformError> {
+    if expected != actual {
+        return Err(TransformError::ChecksumMismatch(expected, actual));
+    }
+    Ok(())
+}
+
+/// Decode a single frame starting after its magic number and append it to `result`
+fn decode_frame(source: &[u8], i: &mut usize, result: &mut Vec<u8>) -> Result<(), TransformError> {
+    let start = *i;
+    let descriptor = source
+        .get(start..start + 2)
+        .ok_or(TransformError::TruncatedBuffer)?;
+    let (flg, bd) = (descriptor[0], descriptor[1]);
+    if flg >> 6 != 1 || flg & 0b10 != 0 || flg & 1 != 0 || bd & 0b1000_1111 != 0 {
+        return Err(TransformError::InvalidHeader);
+    }
+    let independent = flg & 0b10_0000 != 0;
+    let block_checksum = flg & 0b1_0000 != 0;
+    let content_size = flg & 0b1000 != 0;
+    let content_checksum = flg & 0b100 != 0;
+    let block_size = Lz4BlockSize::from_id(bd >> 4).ok_or(TransformError::InvalidHeader)?;
+    *i += 2;
+    let mut size = None;
+    if content_size {
+        let bytes = source
+            .get(*i..*i + 8)
+            .ok_or(TransformError::TruncatedBuffer)?;
+        let mut buffer = [0u8; 8];
+        buffer.copy_from_slice(bytes);
+        size = Some(u64::from_le_bytes(buffer) as usize);
+        *i += 8;
+    }
+    let hc = *source.get(*i).ok_or(TransformError::TruncatedBuffer)?;
+    check((xxh32(&source[start..*i]) >> 8) & 0xFF, hc as u32)?;
+    *i += 1;
+
+    let first = result.len();
+    loop {
+        let header = read_u32(source, i)?;
+        if header == 0 {
+            break;
+        }
+        let len = (header & !UNCOMPRESSED_FLAG) as usize;
+        if len > block_size.bytes() {
+            return Err(TransformError::InvalidHeader);
+        }
+        let block = source
+            .get(*i..*i + len)
+            .ok_or(TransformError::TruncatedBuffer)?;
+        *i += len;
+        if block_checksum {
+            check(xxh32(block), read_u32(source, i)?)?;
+        }
+        if header & UNCOMPRESSED_FLAG != 0 {
+            result.extend_from_slice(block);
+        } else {
+            let history = if independent { result.len() } else { first };
+            decompress_block(block, result, history, block_size.bytes())?;
+        }
+    }
+    if let Some(size) = size {
+        if size != result.len() - first {
+            return Err(TransformError::SizeMismatch(size, result.len() - first));
+        }
+    }
+    if content_checksum {
+        check(xxh32(&result[first..]), read_u32(source, i)?)?;
+    }
+    Ok(())
+}
+
+/// Implementation of the Transform trait for LZ4 frames
+impl Transform for Lz4Frame {
+    fn transform(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let mut result: Vec<u8> = Vec::with_capacity(source.len() + 32);
+        result.extend_from_slice(&LZ4_MAGIC.to_le_bytes());
+        let flg = 0b0110_0000
+            | (self.block_checksum as u8) << 4
+            | (self.content_size as u8) << 3
+            | (self.content_checksum as u8) << 2;
+        result.push(flg);
+        result.push((self.block_size as u8) << 4);
+        if self.content_size {
+            result.extend_from_slice(&(source.len() as u64).to_le_bytes());
+        }
+        let hc = (xxh32(&result[4..]) >> 8) as u8;
+        result.push(hc);
+        for chunk in source.chunks(self.block_size.bytes()) {
+            let compressed = compress_block(chunk);
+            let (header, block) = if compressed.len() < chunk.len() {
+                (compressed.len() as u32, compressed.as_slice())
+            } else {
+                (chunk.len() as u32 | UNCOMPRESSED_FLAG, chunk)
+            };
+            result.extend_from_slice(&header.to_le_bytes());
+            result.extend_from_slice(block);
+            if self.block_checksum {
+                result.extend_from_slice(&xxh32(block).to_le_bytes());
+            }
+        }
+        result.extend_from_slice(&0u32.to_le_bytes());
+        if self.content_checksum {
+            result.extend_from_slice(&xxh32(source).to_le_bytes());
+        }
+        debug!(
+            "LZ4 frame compressed {} bytes into {}",
+            source.len(),
+            result.len()
+        );
+        Ok(result)
+    }
+
+    fn reverse(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let mut result: Vec<u8> = Vec::with_capacity(source.len() * 2);
+        let mut i = 0;
+        while i < source.len() {
+            let magic = read_u32(source, &mut i)?;
+            if magic & 0xFFFF_FFF0 == SKIPPABLE_MAGIC {
+                let len = read_u32(source, &mut i)? as usize;
+                i = i.checked_add(len).ok_or(TransformError::TruncatedBuffer)?;
+                if i > source.len() {
+                    return Err(TransformError::TruncatedBuffer);
+                }
+            } else if magic == LZ4_MAGIC {
+                decode_frame(source, &mut i, &mut result)?;
+            } else {
+                return Err(TransformError::InvalidHeader);
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{
+        random_roundtrip, random_roundtrip_with, roundtrip, short_roundtrip_with, transform,
+    };
+    use crate::DataType;
+    use std::io::{Read, Write};
+
+    const OPTIONS: [(Lz4BlockSize, bool, bool, bool); 3] = [
+        (Lz4BlockSize::Max64KB, true, false, true),
+        (Lz4BlockSize::Max256KB, false, false, false),
+        (Lz4BlockSize::Max1MB, true, true, true),
+    ];
+
+    fn repetitive(size: usize) -> Vec<u8> {
+        "to be or not to be, that is the question. "
+            .bytes()
+            .cycle()
+            .enumerate()
+            .map(|(i, b)| if i % 1_000 == 0 { (i / 1_000) as u8 } else { b })
+            .take(size)
+            .collect()
+    }
+
+    #[test]
+    fn test_block_transforms() {
+        transform::<Lz4Block>(&[1, 2, 3], &[0x30, 1, 2, 3]);
+        // literals, one overlapping match and the last five literals
+        let mut input = vec![0u8; 20];
+        input[15..].copy_from_slice(&[1, 2, 3, 4, 5]);
+        transform::<Lz4Block>(&input, &[0x1A, 0, 1, 0, 0x50, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_block_invalid_offset() {
+        let mut model = Lz4Block::new();
+        assert!(model.reverse(&[0x10, 7, 2, 0, 0x00]).is_err());
+        assert!(model.reverse(&[0x10, 7, 0, 0, 0x00]).is_err());
+        assert!(model.reverse(&[0x20, 7]).is_err());
+    }
+
+    #[test]
+    fn test_empty_frame() {
+        // empty frame as written by the reference implementation
+        let frame = [
+            0x04, 0x22, 0x4D, 0x18, 0x64, 0x40, 0xA7, 0, 0, 0, 0, 0x05, 0x5D, 0xCC, 0x02,
+        ];
+        let mut model = Lz4Frame::new();
+        assert!(model.reverse(&frame).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_handcrafted_frame() {
+        let content = "compression".as_bytes();
+        let mut frame: Vec<u8> = vec![0x04, 0x22, 0x4D, 0x18];
+        // independent blocks, block and content checksums, content size
+        frame.extend_from_slice(&[0x7C, 0x40]);
+        frame.extend_from_slice(&(content.len() as u64).to_le_bytes());
+        frame.push((xxh32(&frame[4..]) >> 8) as u8);
+        // stored block
+        frame.extend_from_slice(&(content.len() as u32 | UNCOMPRESSED_FLAG).to_le_bytes());
+        frame.extend_from_slice(content);
+        frame.extend_from_slice(&xxh32(content).to_le_bytes());
+        frame.extend_from_slice(&[0, 0, 0, 0]);
+        frame.extend_from_slice(&xxh32(content).to_le_bytes());
+        let mut model = Lz4Frame::new();
+        assert_eq!(model.reverse(&frame).unwrap(), content);
+
+        // skippable frame and concatenation
+        let mut stream: Vec<u8> = vec![0x5A, 0x2A, 0x4D, 0x18, 3, 0, 0, 0, 1, 2, 3];
+        stream.extend_from_slice(&frame);
+        stream.extend_from_slice(&frame);
+        assert_eq!(model.reverse(&stream).unwrap(), content.repeat(2));
+
+        // corrupted content
+        let last = frame.len() - 9;
+        frame[last] ^= 1;
+        assert!(model.reverse(&frame).is_err());
+    }
+
+    #[test]
+    fn test_block_size_limit() {
+        // independent blocks of at most 64KB without checksums, containing one
+        // literal followed by a match with `extra` length bytes of 255
+        let frame = |extra: usize| {
+            let mut frame: Vec<u8> = vec![0x04, 0x22, 0x4D, 0x18, 0x60, 0x40];
+            frame.push((xxh32(&frame[4..]) >> 8) as u8);
+            let mut block = vec![0x1F, 7, 1, 0];
+            block.extend(vec![255; extra]);
+            block.extend_from_slice(&[0, 0x00]);
+            frame.extend_from_slice(&(block.len() as u32).to_le_bytes());
+            frame.extend_from_slice(&block);
+            frame.extend_from_slice(&[0, 0, 0, 0]);
+            frame
+        };
+        let mut model = Lz4Frame::new();
+        assert_eq!(
+            model.reverse(&frame(256)).unwrap(),
+            vec![7; 1 + MIN_MATCH + 15 + 255 * 256]
+        );
+        assert!(matches!(
+            model.reverse(&frame(257)),
+            Err(TransformError::InvalidHeader)
+        ));
+        assert!(matches!(
+            model.reverse(&frame(60_000)),
+            Err(TransformError::InvalidHeader)
+        ));
+    }
+
+    #[test]
+    fn test_frame_checksums() {
+        let content = repetitive(10_000);
+        let mut model = Lz4Frame::with_options(Lz4BlockSize::Max64KB, true, true, false);
+        let mut frame = model.transform(&content).unwrap();
+        let n = frame.len();
+        let expected = xxh32(&content).to_le_bytes();
+        assert_eq!(frame[n - 4..], expected);
+        frame[n - 1] ^= 1;
+        assert!(matches!(
+            model.reverse(&frame),
+            Err(TransformError::ChecksumMismatch(_, _))
+        ));
+        frame[n - 1] ^= 1;
+        frame[6] ^= 1;
+        assert!(model.reverse(&frame).is_err());
+    }
+
+    #[test]
+    fn test_reference_interoperability() {
+        for size in [1, 12, 13, 100, 70_000, 300_000].iter() {
+            let content = repetitive(*size);
+
+            let mut model = Lz4Block::new();
+            let block = model.transform(&content).unwrap();
+            assert_eq!(lz4_flex::block::decompress(&block, *size).unwrap(), content);
+            let block = lz4_flex::block::compress(&content);
+            assert_eq!(model.reverse(&block).unwrap(), content);
+
+            for (bs, block_checksum, content_checksum, content_size) in OPTIONS.iter() {
+                let mut model =
+                    Lz4Frame::with_options(*bs, *block_checksum, *content_checksum, *content_size);
+                let frame = model.transform(&content).unwrap();
+                let mut decoded = Vec::new();
+                lz4_flex::frame::FrameDecoder::new(frame.as_slice())
+                    .read_to_end(&mut decoded)
+                    .unwrap();
+                assert_eq!(decoded, content);
+            }
+
+            for linked in [false, true].iter() {
+                let info = lz4_flex::frame::FrameInfo::new()
+                    .block_size(lz4_flex::frame::BlockSize::Max64KB)
+                    .block_mode(if *linked {
+                        lz4_flex::frame::BlockMode::Linked
+                    } else {
+                        lz4_flex::frame::BlockMode::Independent
+                    })
+                    .block_checksums(true)
+                    .content_checksum(true);
+                let mut encoder = lz4_flex::frame::FrameEncoder::with_frame_info(info, Vec::new());
+                encoder.write_all(&content).unwrap();
+                let frame = encoder.finish().unwrap();
+                assert_eq!(Lz4Frame::new().reverse(&frame).unwrap(), content);
+            }
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        roundtrip::<Lz4Block>("compressioncompressioncompression".as_bytes());
+        roundtrip::<Lz4Block>(&[0; 10_000]);
+        roundtrip::<Lz4Frame>("compressioncompressioncompression".as_bytes());
+        roundtrip::<Lz4Frame>(&repetitive(100_000));
+    }
+
+    #[test]
+    fn test_short_roundtrip() {
+        short_roundtrip_with(Lz4Block::new);
+        short_roundtrip_with(Lz4Frame::new);
+        transform::<Lz4Block>(&[], &[0]);
+    }
+
+    #[test]
+    fn test_random_roundtrip() {
+        random_roundtrip::<Lz4Block>(10, 10_000);
+        random_roundtrip::<Lz4Frame>(10, 10_000);
+        for (bs, block_checksum, content_checksum, content_size) in OPTIONS.iter() {
+            random_roundtrip_with(
+                || Lz4Frame::with_options(*bs, *block_checksum, *content_checksum, *content_size),
+                5,
+                100_000,
+            );
+        }
+    }
+
+    #[test]
+    fn test_accepts() {
+        for dtype in [DataType::U8, DataType::I64, DataType::F32].iter() {
+            assert!(Lz4Block::new().accepts(*dtype));
+            assert!(Lz4Frame::new().accepts(*dtype));
+        }
+    }
+}
@@ -0,0 +1,360 @@
+//! LZ77 Transformation
+//!
+//! Implementation of the dictionary based
+//! [LZ77](https://en.wikipedia.org/wiki/LZ77_and_LZ78) compression
+//! using hash chains to find matches in a sliding window.
+use crate::varint::{push_varint, read_varint};
+use crate::{Transform, TransformError};
+use log::debug;
+
+/// Shortest match which is emitted as token
+pub const MIN_MATCH: usize = 3;
+/// Longest match which is emitted as token
+pub const MAX_MATCH: usize = 258;
+
+const DEFAULT_WINDOW: usize = 32_768;
+const HASH_BITS: u32 = 15;
+const MAX_CHAIN: usize = 128;
+const NONE: usize = usize::MAX;
+
+/// Token of a LZ77 encoded stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token {
+    /// Single byte without match
+    Literal(u8),
+    /// Copy of `length` bytes starting `distance` bytes before the current position
+    Match { length: usize, distance: usize },
+}
+
+/// Hash chains of all positions inserted so far
+struct Matcher<'a> {
+    source: &'a [u8],
+    head: Vec<usize>,
+    prev: Vec<usize>,
+    inserted: usize,
+    window: usize,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(source: &'a [u8], window: usize) -> Self {
+        Matcher {
+            source,
+            head: vec![NONE; 1 << HASH_BITS],
+            prev: vec![NONE; source.len()],
+            inserted: 0,
+            window,
+        }
+    }
+    fn hash(&self, pos: usize) -> usize {
+        let s = &self.source[pos..pos + MIN_MATCH];
+        let h = (s[0] as u32) << 16 | (s[1] as u32) << 8 | s[2] as u32;
+        (h.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+    }
+    /// Insert all positions before `pos` into the hash chains
+    fn insert_until(&mut self, pos: usize) {
+        let end = pos.min(self.source.len().saturating_sub(MIN_MATCH - 1));
+        while self.inserted < end {
+            let h = self.hash(self.inserted);
+            self.prev[self.inserted] = self.head[h];
+            self.head[h] = self.inserted;
+            self.inserted += 1;
+        }
+        self.inserted = self.inserted.max(pos);
+    }
+    /// Find the longest match for `pos` as `(length, distance)`
+    fn longest(&mut self, pos: usize) -> (usize, usize) {
+        self.insert_until(pos);
+        let limit = MAX_MATCH.min(self.source.len() - pos);
+        if limit < MIN_MATCH {
+            return (0, 0);
+        }
+        let (mut best, mut distance) = (0, 0);
+        let mut candidate = self.head[self.hash(pos)];
+        let mut chain = 0;
+        while candidate != NONE && pos - candidate <= self.window && chain < MAX_CHAIN {
+            let length = self.source[candidate..]
+                .iter()
+                .zip(self.source[pos..pos + limit].iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+            if length > best {
+                best = length;
+                distance = pos - candidate;
+                if best == limit {
+                    break;
+                }
+            }
+            candidate = self.prev[candidate];
+            chain += 1;
+        }
+        (best, distance)
+    }
+}
+
+/// LZ77 struct to save the size of the sliding window and the matching strategy
+///
+/// Repeated byte sequences are replaced by matches referencing an earlier occurrence
+/// at most `window` bytes before. Candidates are found with hash chains over
+/// the next [`MIN_MATCH`] bytes. With lazy matching a match is deferred if
+/// the next position starts a longer match.
+///
+/// # Byte representation
+/// The tokens can be accessed via [`Lz77::tokens`] and [`Lz77::from_tokens`],
+/// e.g. for entropy coding of literals, lengths and distances.
+/// The implementation of the `Transform` trait writes groups of up to eight tokens
+/// prefixed by a flag byte. The lowest bit belongs to the first token and is set for matches.
+/// A literal is written as its byte, a match as the variable-length integers
+/// `length - MIN_MATCH` and `distance - 1`.
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress_transformation::{Lz77, Token, Transform};
+/// let model = Lz77::new();
+/// let tokens = model.tokens("abcabcabc".as_bytes());
+/// assert_eq!(tokens[3], Token::Match { length: 6, distance: 3 });
+///
+/// let mut model = Lz77::new();
+/// let transformed = model.transform("abcabcabc".as_bytes()).unwrap();
+/// assert_eq!(transformed, [0b1000, 97, 98, 99, 3, 2]);
+/// ```
+#[derive(Debug)]
+pub struct Lz77 {
+    window: usize,
+    lazy: bool,
+}
+
+impl Lz77 {
+    pub fn new() -> Self {
+        Self::with_window_and_lazy(DEFAULT_WINDOW, true)
+    }
+    /// Generate LZ77 with a sliding window of `window` bytes (at least 1)
+    pub fn with_window_and_lazy(window: usize, lazy: bool) -> Self {
+        Lz77 {
+            window: window.max(1),
+            lazy,
+        }
+    }
+    /// Split the data into literal and match tokens
+    pub fn tokens(&self, source: &[u8]) -> Vec<Token> {
+        let mut matcher = Matcher::new(source, self.window);
+        let mut result: Vec<Token> = Vec::new();
+        let mut i = 0;
+        while i < source.len() {
+            let (length, distance) = matcher.longest(i);
+            if length < MIN_MATCH {
+                result.push(Token::Literal(source[i]));
+                i += 1;
+                continue;
+            }
+            if self.lazy && length < MAX_MATCH && matcher.longest(i + 1).0 > length {
+                result.push(Token::Literal(source[i]));
+                i += 1;
+                continue;
+            }
+            result.push(Token::Match { length, distance });
+            i += length;
+        }
+        result
+    }
+    /// Reconstruct the data from literal and match tokens
+    pub fn from_tokens(tokens: &[Token]) -> Result<Vec<u8>, TransformError> {
+        let mut result: Vec<u8> = Vec::new();
+        for token in tokens.iter() {
+            match *token {
+                Token::Literal(byte) => result.push(byte),
+                Token::Match { length, distance } => {
+                    if distance == 0 || distance > result.len() {
+                        return Err(TransformError::InvalidDistance(distance));
+                    }
+                    let start = result.len() - distance;
+                    // byte wise, because the match may overlap with itself
+                    for k in 0..length {
+                        result.push(result[start + k]);
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl Default for Lz77 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Implementation of the Transform trait for LZ77
+impl Transform for Lz77 {
+    fn transform(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let tokens = self.tokens(source);
+        let mut result: Vec<u8> = Vec::with_capacity(source.len());
+        for group in tokens.chunks(8) {
+            let flags = result.len();
+            result.push(0);
+            for (k, token) in group.iter().enumerate() {
+                match *token {
+                    Token::Literal(byte) => result.push(byte),
+                    Token::Match { length, distance } => {
+                        result[flags] |= 1 << k;
+                        push_varint(&mut result, length - MIN_MATCH);
+                        push_varint(&mut result, distance - 1);
+                    }
+                }
+            }
+        }
+        debug!(
+            "LZ77 encoded {} bytes into {} tokens and {} bytes",
+            source.len(),
+            tokens.len(),
+            result.len()
+        );
+        Ok(result)
+    }
+
+    fn reverse(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let mut tokens: Vec<Token> = Vec::new();
+        let mut i = 0;
+        while i < source.len() {
+            let flags = source[i];
+            i += 1;
+            for k in 0..8 {
+                if i == source.len() {
+                    break;
+                }
+                if flags >> k & 1 == 0 {
+                    tokens.push(Token::Literal(source[i]));
+                    i += 1;
+                } else {
+                    let length = read_varint(source, &mut i)?.saturating_add(MIN_MATCH);
+                    if length > MAX_MATCH {
+                        return Err(TransformError::InvalidRunLength);
+                    }
+                    let distance = read_varint(source, &mut i)?
+                        .checked_add(1)
+                        .ok_or(TransformError::InvalidDistance(usize::MAX))?;
+                    tokens.push(Token::Match { length, distance });
+                }
+            }
+        }
+        Self::from_tokens(&tokens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{
+        random_roundtrip, random_roundtrip_with, roundtrip, roundtrip_with, short_roundtrip_with,
+    };
+
+    fn literals(s: &str) -> Vec<Token> {
+        s.bytes().map(Token::Literal).collect()
+    }
+
+    #[test]
+    fn test_tokens() {
+        let model = Lz77::new();
+        let mut expected = literals("abc");
+        expected.push(Token::Match {
+            length: 9,
+            distance: 3,
+        });
+        assert_eq!(model.tokens("abcabcabcabc".as_bytes()), expected);
+        let tokens = model.tokens(&[b'a'; 300]);
+        assert_eq!(
+            tokens[..2],
+            [
+                Token::Literal(b'a'),
+                Token::Match {
+                    length: MAX_MATCH,
+                    distance: 1
+                }
+            ]
+        );
+        assert_eq!(Lz77::from_tokens(&tokens).unwrap(), [b'a'; 300]);
+        assert_eq!(model.tokens("abcd".as_bytes()), literals("abcd"));
+    }
+
+    #[test]
+    fn test_lazy_matching() {
+        let input = "abc_bcde_abcde".as_bytes();
+        let mut greedy = literals("abc_bcde_");
+        greedy.push(Token::Match {
+            length: 3,
+            distance: 9,
+        });
+        greedy.extend(literals("de"));
+        assert_eq!(Lz77::with_window_and_lazy(100, false).tokens(input), greedy);
+        let mut lazy = literals("abc_bcde_a");
+        lazy.push(Token::Match {
+            length: 4,
+            distance: 6,
+        });
+        assert_eq!(Lz77::with_window_and_lazy(100, true).tokens(input), lazy);
+    }
+
+    #[test]
+    fn test_window() {
+        let input = "abcdefgh_abcdefgh".as_bytes();
+        assert_eq!(
+            Lz77::with_window_and_lazy(8, true).tokens(input).len(),
+            input.len()
+        );
+        assert_eq!(Lz77::with_window_and_lazy(9, true).tokens(input).len(), 10);
+    }
+
+    #[test]
+    fn test_invalid_distance() {
+        let tokens = [
+            Token::Literal(1),
+            Token::Match {
+                length: 3,
+                distance: 2,
+            },
+        ];
+        assert!(Lz77::from_tokens(&tokens).is_err());
+        let mut model = Lz77::new();
+        assert!(model.reverse(&[0b10, 1, 0, 0]).is_ok());
+        assert!(model.reverse(&[0b10, 1, 0, 1]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_length() {
+        let mut model = Lz77::new();
+        assert_eq!(model.reverse(&[0b10, 1, 0xff, 0x01, 0]).unwrap(), [1; 259]);
+        assert!(matches!(
+            model.reverse(&[0b10, 1, 0x80, 0x02, 0]),
+            Err(TransformError::InvalidRunLength)
+        ));
+        let overflow = [
+            0b10, 1, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01, 0,
+        ];
+        assert!(model.reverse(&overflow).is_err());
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        roundtrip::<Lz77>("compressioncompressioncompression".as_bytes());
+        roundtrip::<Lz77>(&[8, 2, 2, 2, 24, 32, 32, 1, 24]);
+        roundtrip::<Lz77>(&[0; 10_000]);
+        roundtrip_with(
+            &mut Lz77::with_window_and_lazy(1, false),
+            &[1, 2, 1, 1, 1, 1, 2],
+        );
+    }
+
+    #[test]
+    fn test_short_roundtrip() {
+        short_roundtrip_with(Lz77::new);
+        short_roundtrip_with(|| Lz77::with_window_and_lazy(1, false));
+        assert!(Lz77::new().transform(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_random_roundtrip() {
+        random_roundtrip::<Lz77>(10, 10_000);
+        random_roundtrip_with(|| Lz77::with_window_and_lazy(16, false), 10, 10_000);
+    }
+}
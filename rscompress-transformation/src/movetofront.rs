@@ -1,39 +1,147 @@
+use crate::varint::{push_varint, read_varint};
 use crate::{Transform, TransformError};
 use log::debug;
 
 const ALPHABET_SIZE: u8 = 255; // + 1 number of elements
 
+/// Rank-update policies of the Move-To-Front transformation
+///
+/// The policy decides to which position a symbol is moved after
+/// it has been coded. All policies only depend on already coded
+/// information and are therefore reversible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// Classic MTF: every symbol is moved to the front
+    Front,
+    /// MTF-1: a symbol at position 1 is moved to the front, all others to position 1
+    One,
+    /// MTF-2: like MTF-1, but a symbol at position 1 is only moved to the front
+    /// if the previous rank was not 0
+    Two,
+    /// Sticky MTF: a symbol is moved halfway towards the front
+    Sticky,
+}
+
+impl Policy {
+    /// Target position of a symbol found at `pos`, given the previous rank
+    fn target(&self, pos: usize, last: Option<usize>) -> usize {
+        match *self {
+            Policy::Front => 0,
+            Policy::One if pos <= 1 => 0,
+            Policy::Two if pos == 1 && last == Some(0) => 1,
+            Policy::Two if pos <= 1 => 0,
+            Policy::One | Policy::Two => 1,
+            Policy::Sticky => pos / 2,
+        }
+    }
+}
+
+/// State semantics of the Move-To-Front transformation
+///
+/// The forward and the reverse direction keep separate tables.
+/// This way a single instance can transform and reverse data independently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Continuity {
+    /// Consecutive calls are treated as one continuous stream.
+    /// Chunks must be reversed in the same order they were transformed.
+    Stream,
+    /// Every call starts with a fresh table.
+    /// Chunks can be reversed independently of each other.
+    Block,
+}
+
+/// Table of symbols ordered by rank
+#[derive(Debug)]
+struct Ranking {
+    table: Vec<u8>,
+    last: Option<usize>,
+}
+
+impl Ranking {
+    fn new() -> Self {
+        Ranking {
+            table: (0u8..=ALPHABET_SIZE).collect(),
+            last: None,
+        }
+    }
+    fn update(&mut self, policy: Policy, pos: usize) {
+        let target = policy.target(pos, self.last);
+        self.table[target..=pos].rotate_right(1);
+        self.last = Some(pos);
+    }
+    fn copy_from(&mut self, other: &Ranking) {
+        self.table.copy_from_slice(&other.table);
+        self.last = other.last;
+    }
+}
+
 /// Move-to-Front Transformation
 ///
-/// Implementation of the Burrow-Wheeler Transformation as
-/// described [here](https://en.wikipedia.org/wiki/Burrows%E2%80%93Wheeler_transform).
+/// Implementation of the Move-To-Front Transformation as
+/// described [here](https://en.wikipedia.org/wiki/Move-to-front_transform).
 ///
 /// # Algorithm
 ///
-/// Lorem ipsum
+/// Each symbol is replaced by its rank in a table of all symbols.
+/// Afterwards the table is updated according to the [`Policy`]
+/// of the transformation. The classic variant moves the symbol to the front,
+/// such that recently seen symbols get small ranks. The variants
+/// MTF-1, MTF-2 and Sticky MTF move symbols less aggressively, which
+/// results in better compression after the Burrow-Wheeler Transformation.
+///
+/// # State
+///
+/// By default the transformation treats consecutive calls as one [`Continuity::Stream`].
+/// The state of the forward and reverse direction is kept separately,
+/// so chunks transformed one after another must be reversed in the same order.
+/// Using [`Continuity::Block`] every call starts from the initial table.
+/// Calling [`MoveToFront::reset`] restarts both directions.
+/// The metadata of the transformation is the table at the start of the last call,
+/// such that a single chunk of a stream can be reversed by a new model.
 ///
 /// # Example
 ///
 /// ```rust
-/// use rscompress_transformation::MoveToFront;
+/// use rscompress_transformation::{Continuity, MoveToFront, Policy, Transform};
+/// let mut model = MoveToFront::new();
+/// let first = model.transform("banana".as_bytes()).unwrap();
+/// let second = model.transform("bandana".as_bytes()).unwrap();
+/// assert_eq!(model.reverse(&first).unwrap(), "banana".as_bytes());
+/// assert_eq!(model.reverse(&second).unwrap(), "bandana".as_bytes());
+///
+/// let mtf1 = MoveToFront::with_policy_and_continuity(Policy::One, Continuity::Block);
 /// ```
 ///
 #[derive(Debug)]
 pub struct MoveToFront {
-    table: Vec<u8>,
+    forward: Ranking,
+    backward: Ranking,
+    initial: Ranking,
+    policy: Policy,
+    continuity: Continuity,
 }
 
 impl MoveToFront {
     pub fn new() -> Self {
-        let table: Vec<u8> = (0u8..=ALPHABET_SIZE).collect();
-        MoveToFront { table }
+        Self::with_policy(Policy::Front)
     }
-    pub fn reset(&mut self) {
-        let table: Vec<u8> = (0u8..=ALPHABET_SIZE).collect();
-        self.table = table;
+    pub fn with_policy(policy: Policy) -> Self {
+        Self::with_policy_and_continuity(policy, Continuity::Stream)
     }
-    fn rotate(&mut self, pos: usize) {
-        self.table[..pos].rotate_right(1);
+    pub fn with_policy_and_continuity(policy: Policy, continuity: Continuity) -> Self {
+        MoveToFront {
+            forward: Ranking::new(),
+            backward: Ranking::new(),
+            initial: Ranking::new(),
+            policy,
+            continuity,
+        }
+    }
+    /// Reset the state of both directions
+    pub fn reset(&mut self) {
+        self.forward = Ranking::new();
+        self.backward = Ranking::new();
+        self.initial = Ranking::new();
     }
 }
 
@@ -46,53 +154,115 @@ impl Default for MoveToFront {
 /// Implementation of the Transformation trait for Move-To-Front
 impl Transform for MoveToFront {
     fn transform(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
-        if source.is_empty() {
-            return Err(TransformError::EmptyBufferError);
-        }
         let mut result: Vec<u8> = Vec::with_capacity(source.len());
+        self.transform_into(source, &mut result)?;
+        Ok(result)
+    }
+    fn reverse(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let mut result: Vec<u8> = Vec::with_capacity(source.len());
+        self.reverse_into(source, &mut result)?;
+        Ok(result)
+    }
+    fn transform_into(
+        &mut self,
+        source: &[u8],
+        result: &mut Vec<u8>,
+    ) -> Result<(), TransformError> {
+        if self.continuity == Continuity::Block {
+            self.forward = Ranking::new();
+        }
+        self.initial.copy_from(&self.forward);
+        result.clear();
+        result.reserve(source.len());
         for byte in source.iter() {
             let pos = self
+                .forward
                 .table
                 .iter()
                 .position(|p| p == byte)
-                .ok_or_else(|| TransformError::SymbolNotFound(*byte))?;
+                .ok_or(TransformError::SymbolNotFound(*byte))?;
             debug!("Found {:?} at {:?}", byte, pos);
-            self.rotate(pos + 1);
+            self.forward.update(self.policy, pos);
             result.push(pos as u8);
         }
-        Ok(result)
+        Ok(())
     }
-    fn reverse(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
-        self.reset();
-        if source.is_empty() {
-            return Err(TransformError::EmptyBufferError);
+    fn reverse_into(&mut self, source: &[u8], result: &mut Vec<u8>) -> Result<(), TransformError> {
+        if self.continuity == Continuity::Block {
+            self.backward = Ranking::new();
         }
-        let mut result: Vec<u8> = Vec::with_capacity(source.len());
+        result.clear();
+        result.reserve(source.len());
         for pos in source.iter() {
             let ix = *pos as usize;
-            debug!(
-                "Found element (w/ surround) {:?} [{:?}] {:?}",
-                self.table[ix],
-                self.table[ix - 1],
-                self.table[ix + 1]
-            );
-            result.push(self.table[ix]);
-            self.rotate(ix + 1);
+            let symbol = self.backward.table[ix];
+            debug!("Found {:?} at {:?}", symbol, ix);
+            result.push(symbol);
+            self.backward.update(self.policy, ix);
         }
-        Ok(result)
+        Ok(())
+    }
+
+    /// The table is saved as is, followed by the last rank shifted by one
+    /// as variable-length integer, such that zero represents a missing rank.
+    fn export_metadata(&self) -> Vec<u8> {
+        let mut result = self.initial.table.clone();
+        push_varint(&mut result, self.initial.last.map_or(0, |last| last + 1));
+        result
+    }
+    fn import_metadata(&mut self, metadata: &[u8]) -> Result<(), TransformError> {
+        let size = ALPHABET_SIZE as usize + 1;
+        if metadata.len() <= size {
+            return Err(TransformError::InvalidMetadata);
+        }
+        let mut seen = [false; ALPHABET_SIZE as usize + 1];
+        for symbol in metadata[..size].iter() {
+            if std::mem::replace(&mut seen[*symbol as usize], true) {
+                return Err(TransformError::InvalidMetadata);
+            }
+        }
+        let mut i = size;
+        let last = read_varint(metadata, &mut i).map_err(|_| TransformError::InvalidMetadata)?;
+        if i != metadata.len() || last > size {
+            return Err(TransformError::InvalidMetadata);
+        }
+        self.backward.table.copy_from_slice(&metadata[..size]);
+        self.backward.last = last.checked_sub(1);
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tests::{random_roundtrip, reverse, roundtrip, transform};
+    use crate::tests::{
+        metadata_roundtrip_with, random_roundtrip, random_roundtrip_with, reverse, roundtrip,
+        roundtrip_into, roundtrip_with, short_roundtrip_with, transform, transform_with,
+    };
+
+    const POLICIES: [Policy; 4] = [Policy::Front, Policy::One, Policy::Two, Policy::Sticky];
 
     #[test]
     fn test_easy_transforms() {
         transform::<MoveToFront>("bananaaa".as_bytes(), &[98, 98, 110, 1, 1, 1, 0, 0]);
     }
 
+    #[test]
+    fn test_policy_transforms() {
+        let mut model = MoveToFront::with_policy(Policy::One);
+        transform_with(
+            &mut model,
+            "bananaaa".as_bytes(),
+            &[98, 98, 110, 2, 2, 2, 1, 0],
+        );
+        let mut model = MoveToFront::with_policy(Policy::One);
+        transform_with(&mut model, "aabbbab".as_bytes(), &[97, 1, 98, 1, 0, 1, 1]);
+        let mut model = MoveToFront::with_policy(Policy::Two);
+        transform_with(&mut model, "aabbbab".as_bytes(), &[97, 1, 98, 1, 0, 1, 0]);
+        let mut model = MoveToFront::with_policy(Policy::Sticky);
+        transform_with(&mut model, "aaaa".as_bytes(), &[97, 48, 24, 12]);
+    }
+
     #[test]
     fn test_easy_reverse() {
         reverse::<MoveToFront>(&[98, 98, 110, 1, 1, 1, 0, 0], "bananaaa".as_bytes());
@@ -101,6 +271,91 @@ mod tests {
     #[test]
     fn test_easy_roundtrip() {
         roundtrip::<MoveToFront>("bananaaa".as_bytes());
+        for policy in POLICIES.iter() {
+            roundtrip_with(
+                &mut MoveToFront::with_policy(*policy),
+                "bananaaa".as_bytes(),
+            );
+            roundtrip_with(
+                &mut MoveToFront::with_policy(*policy),
+                "compressioncode".as_bytes(),
+            );
+        }
+    }
+
+    #[test]
+    fn test_stream_chunks() {
+        let chunks = ["bananaaa", "bandana", "", "compressioncode"];
+        for policy in POLICIES.iter() {
+            let mut model = MoveToFront::with_policy(*policy);
+            let mut single = MoveToFront::with_policy(*policy);
+            let whole = single.transform(chunks.concat().as_bytes()).unwrap();
+            let transformed: Vec<Vec<u8>> = chunks
+                .iter()
+                .map(|c| model.transform(c.as_bytes()).unwrap())
+                .collect();
+            assert_eq!(transformed.concat(), whole);
+            for (chunk, t) in chunks.iter().zip(transformed.iter()) {
+                assert_eq!(model.reverse(t).unwrap(), chunk.as_bytes());
+            }
+        }
+    }
+
+    #[test]
+    fn test_block_chunks() {
+        let mut model = MoveToFront::with_policy_and_continuity(Policy::Front, Continuity::Block);
+        let first = model.transform("banana".as_bytes()).unwrap();
+        let second = model.transform("banana".as_bytes()).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(model.reverse(&second).unwrap(), "banana".as_bytes());
+        assert_eq!(model.reverse(&first).unwrap(), "banana".as_bytes());
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut model = MoveToFront::new();
+        let first = model.transform("banana".as_bytes()).unwrap();
+        model.reset();
+        assert_eq!(model.transform("banana".as_bytes()).unwrap(), first);
+    }
+
+    #[test]
+    fn test_reverse_rank_zero() {
+        reverse::<MoveToFront>(&[0, 0, 255, 0], &[0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn test_roundtrip_into() {
+        roundtrip_into::<MoveToFront>("bananaaa".as_bytes());
+        roundtrip_into::<MoveToFront>(&[255, 0, 255, 0]);
+    }
+
+    #[test]
+    fn test_metadata() {
+        let chunks: [&[u8]; 3] = [
+            "banana".as_bytes(),
+            "bandana".as_bytes(),
+            "ananas".as_bytes(),
+        ];
+        metadata_roundtrip_with(MoveToFront::new, &chunks);
+        for policy in POLICIES.iter() {
+            metadata_roundtrip_with(|| MoveToFront::with_policy(*policy), &chunks);
+        }
+        let mut model = MoveToFront::new();
+        let mut metadata = model.export_metadata();
+        assert_eq!(metadata.len(), 257);
+        assert!(model.import_metadata(&metadata).is_ok());
+        metadata[0] = 1;
+        assert!(model.import_metadata(&metadata).is_err());
+        assert!(model.import_metadata(&metadata[..256]).is_err());
+    }
+
+    #[test]
+    fn test_short_roundtrip() {
+        for policy in POLICIES.iter() {
+            short_roundtrip_with(|| MoveToFront::with_policy(*policy));
+        }
+        transform::<MoveToFront>(&[], &[]);
     }
 
     #[test]
@@ -111,4 +366,11 @@ mod tests {
         random_roundtrip::<MoveToFront>(100, 10_000);
         random_roundtrip::<MoveToFront>(100, 10_000);
     }
+
+    #[test]
+    fn test_random_policy_roundtrip() {
+        for policy in POLICIES.iter() {
+            random_roundtrip_with(|| MoveToFront::with_policy(*policy), 20, 10_000);
+        }
+    }
 }
@@ -0,0 +1,185 @@
+//! PackBits Transformation
+//!
+//! Implementation of the PackBits run-length encoding as
+//! described [here](https://en.wikipedia.org/wiki/PackBits).
+use crate::{Transform, TransformError};
+use log::debug;
+
+const MAX_PACKET: usize = 128;
+const NOOP: u8 = 128;
+const DEFAULT_MIN_RUN: usize = 3;
+
+/// PackBits struct to save the minimum length of runs
+///
+/// The data is split into packets, each starting with a header byte `h`.
+///
+/// - `0..=127`: the following `h + 1` bytes are literals
+/// - `129..=255`: the following byte is repeated `257 - h` times
+/// - `128`: no operation, the header is skipped
+///
+/// Runs shorter than `min_run` are written as literals, since a run packet
+/// in the middle of literals costs up to two additional bytes.
+/// Incompressible data grows by at most one byte per 128 bytes.
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress_transformation::{PackBits, Transform};
+/// let mut model = PackBits::with_min_run(2);
+/// let transformed = model.transform(&[7, 7, 7, 7, 1, 2]).unwrap();
+/// assert_eq!(transformed, [253, 7, 1, 1, 2]);
+/// ```
+#[derive(Debug)]
+pub struct PackBits {
+    min_run: usize,
+}
+
+impl PackBits {
+    pub fn new() -> Self {
+        Self::with_min_run(DEFAULT_MIN_RUN)
+    }
+    /// Generate PackBits with a minimum run length (clamped to `2..=128`)
+    pub fn with_min_run(min_run: usize) -> Self {
+        PackBits {
+            min_run: min_run.clamp(2, MAX_PACKET),
+        }
+    }
+}
+
+impl Default for PackBits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Append pending literals as literal packets
+fn flush_literals(result: &mut Vec<u8>, literals: &[u8]) {
+    for chunk in literals.chunks(MAX_PACKET) {
+        result.push((chunk.len() - 1) as u8);
+        result.extend_from_slice(chunk);
+    }
+}
+
+/// Implementation of the Transform trait for PackBits
+impl Transform for PackBits {
+    fn transform(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let mut result: Vec<u8> = Vec::with_capacity(source.len() + source.len() / MAX_PACKET);
+        let mut literal_start = 0;
+        let mut i = 0;
+        while i < source.len() {
+            let run = source[i..]
+                .iter()
+                .take(MAX_PACKET)
+                .take_while(|&&b| b == source[i])
+                .count();
+            if run >= self.min_run {
+                flush_literals(&mut result, &source[literal_start..i]);
+                result.push((257 - run) as u8);
+                result.push(source[i]);
+                i += run;
+                literal_start = i;
+            } else {
+                i += 1;
+            }
+        }
+        flush_literals(&mut result, &source[literal_start..]);
+        debug!(
+            "PackBits encoded {} bytes into {}",
+            source.len(),
+            result.len()
+        );
+        Ok(result)
+    }
+
+    fn reverse(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let mut result: Vec<u8> = Vec::with_capacity(source.len());
+        let mut i = 0;
+        while i < source.len() {
+            let header = source[i];
+            i += 1;
+            match header {
+                NOOP => {}
+                h if h < NOOP => {
+                    let end = i + h as usize + 1;
+                    let literals = source.get(i..end).ok_or(TransformError::TruncatedBuffer)?;
+                    result.extend_from_slice(literals);
+                    i = end;
+                }
+                h => {
+                    let byte = *source.get(i).ok_or(TransformError::TruncatedBuffer)?;
+                    result.resize(result.len() + 257 - h as usize, byte);
+                    i += 1;
+                }
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{
+        random_roundtrip, random_roundtrip_with, reverse, roundtrip, short_roundtrip_with,
+        transform,
+    };
+
+    #[test]
+    fn test_easy_transforms() {
+        transform::<PackBits>(&[1, 2, 3], &[2, 1, 2, 3]);
+        transform::<PackBits>(&[5, 5, 5, 5], &[253, 5]);
+        transform::<PackBits>(&[1, 5, 5, 2], &[3, 1, 5, 5, 2]);
+        transform::<PackBits>(&[9; 130], &[129, 9, 1, 9, 9]);
+    }
+
+    #[test]
+    fn test_easy_reverses() {
+        // example from the Apple technical note TN1023
+        reverse::<PackBits>(
+            &[
+                0xFE, 0xAA, 0x02, 0x80, 0x00, 0x2A, 0xFD, 0xAA, 0x03, 0x80, 0x00, 0x2A, 0x22, 0xF7,
+                0xAA,
+            ],
+            &[
+                0xAA, 0xAA, 0xAA, 0x80, 0x00, 0x2A, 0xAA, 0xAA, 0xAA, 0xAA, 0x80, 0x00, 0x2A, 0x22,
+                0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA,
+            ],
+        );
+        reverse::<PackBits>(&[NOOP, 0, 1], &[1]);
+    }
+
+    #[test]
+    fn test_truncated_reverses() {
+        let mut model = PackBits::new();
+        assert!(model.reverse(&[3, 1, 2]).is_err());
+        assert!(model.reverse(&[250]).is_err());
+    }
+
+    #[test]
+    fn test_reduces_length() {
+        let mut model = PackBits::new();
+        let result = model.transform(&[0u8; 1_000]).unwrap();
+        assert_eq!(result.len(), 16);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        roundtrip::<PackBits>(&[8, 2, 2, 2, 24, 32, 32, 1, 24]);
+        roundtrip::<PackBits>(&[8; 1_000]);
+        roundtrip::<PackBits>("compressioncode".as_bytes());
+    }
+
+    #[test]
+    fn test_short_roundtrip() {
+        short_roundtrip_with(PackBits::new);
+        short_roundtrip_with(|| PackBits::with_min_run(2));
+        transform::<PackBits>(&[], &[]);
+    }
+
+    #[test]
+    fn test_random_roundtrip() {
+        random_roundtrip::<PackBits>(100, 10_000);
+        random_roundtrip_with(|| PackBits::with_min_run(2), 100, 10_000);
+        random_roundtrip_with(|| PackBits::with_min_run(128), 100, 10_000);
+    }
+}
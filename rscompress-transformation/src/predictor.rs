@@ -0,0 +1,431 @@
+//! Prediction Residual Transformation
+//!
+//! Implementation of spatial predictors for gridded data, e.g. the
+//! [Lorenzo predictor](https://doi.org/10.1111/1467-8659.00681) as used by
+//! [SZ](https://szcompressor.org/).
+use crate::element::{from_elements, to_elements};
+use crate::{DataType, Primitive, Transform, TransformError};
+use log::debug;
+use std::marker::PhantomData;
+
+/// Shape of a gridded dataset
+///
+/// The first dimension `nx` varies fastest in memory,
+/// i.e. the element `(x, y, z)` is found at `x + nx * (y + ny * z)`.
+/// Two-dimensional data has `nz = 1` and one-dimensional data `ny = nz = 1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shape {
+    pub nx: usize,
+    pub ny: usize,
+    pub nz: usize,
+}
+
+impl Shape {
+    pub fn new(nx: usize, ny: usize, nz: usize) -> Self {
+        Shape { nx, ny, nz }
+    }
+    /// Number of elements in the grid
+    pub fn len(&self) -> usize {
+        self.nx * self.ny * self.nz
+    }
+    /// Check if the grid has no elements
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Trait for elements which can be predicted and restored exactly from residuals
+///
+/// Residuals are unsigned integers of the same width as the element.
+/// Integers use wrapping arithmetic. Floats are predicted in floating point arithmetic,
+/// but the residual is the difference of their bit patterns mapped to ordered integers.
+/// Therefore all values, including NaN and infinities, are restored bit-exactly.
+pub trait Predictable: Primitive {
+    fn zero() -> Self;
+    fn add(self, other: Self) -> Self;
+    fn sub(self, other: Self) -> Self;
+    /// Residual of the value with respect to the prediction
+    fn residual(self, prediction: Self) -> u64;
+    /// Restore the value from the residual and the prediction
+    fn restore(residual: u64, prediction: Self) -> Self;
+}
+
+macro_rules! impl_predictable_integer {
+    ($($t:ty => $u:ty),*) => {
+        $(
+            impl Predictable for $t {
+                fn zero() -> Self {
+                    0
+                }
+                fn add(self, other: Self) -> Self {
+                    self.wrapping_add(other)
+                }
+                fn sub(self, other: Self) -> Self {
+                    self.wrapping_sub(other)
+                }
+                fn residual(self, prediction: Self) -> u64 {
+                    self.wrapping_sub(prediction) as $u as u64
+                }
+                fn restore(residual: u64, prediction: Self) -> Self {
+                    prediction.wrapping_add(residual as $u as $t)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_predictable_float {
+    ($($t:ty => $u:ty),*) => {
+        $(
+            impl Predictable for $t {
+                fn zero() -> Self {
+                    0.0
+                }
+                fn add(self, other: Self) -> Self {
+                    self + other
+                }
+                fn sub(self, other: Self) -> Self {
+                    self - other
+                }
+                fn residual(self, prediction: Self) -> u64 {
+                    ordered(self.to_bits()).wrapping_sub(ordered(prediction.to_bits())) as u64
+                }
+                fn restore(residual: u64, prediction: Self) -> Self {
+                    let bits = ordered(prediction.to_bits()).wrapping_add(residual as $u);
+                    <$t>::from_bits(unordered(bits))
+                }
+            }
+
+            impl Ordered for $u {
+                const SIGN: Self = 1 << (<$u>::BITS - 1);
+            }
+        )*
+    };
+}
+
+/// Mapping of float bit patterns to integers with the same order as the floats
+trait Ordered:
+    Copy
+    + PartialEq
+    + std::ops::Not<Output = Self>
+    + std::ops::BitXor<Output = Self>
+    + std::ops::BitAnd<Output = Self>
+{
+    const SIGN: Self;
+}
+
+fn ordered<U: Ordered>(bits: U) -> U {
+    if bits & U::SIGN == U::SIGN {
+        !bits
+    } else {
+        bits ^ U::SIGN
+    }
+}
+
+fn unordered<U: Ordered>(bits: U) -> U {
+    if bits & U::SIGN == U::SIGN {
+        bits ^ U::SIGN
+    } else {
+        !bits
+    }
+}
+
+impl_predictable_integer!(u8 => u8, u16 => u16, u32 => u32, u64 => u64, i8 => u8, i16 => u16, i32 => u32, i64 => u64);
+impl_predictable_float!(f32 => u32, f64 => u64);
+
+/// Predictors for gridded data
+///
+/// Neighbours outside of the grid are treated as zero.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Predictor {
+    /// Previous element in memory order
+    LastValue,
+    /// Plane through the three preceding neighbours in the same `xy` slice
+    /// i.e. `f(x-1, y) + f(x, y-1) - f(x-1, y-1)`
+    Plane,
+    /// Lorenzo predictor using all seven preceding neighbours of the `2x2x2` cube
+    Lorenzo,
+}
+
+impl Predictor {
+    /// Predict the element at `index` from the preceding elements of `data`
+    ///
+    /// Only elements before `index` in memory order are used, such that
+    /// the prediction can be repeated while reconstructing the data.
+    pub fn predict<T: Predictable>(&self, data: &[T], shape: &Shape, index: usize) -> T {
+        let (nx, ny) = (shape.nx, shape.ny);
+        let x = index % nx;
+        let y = (index / nx) % ny;
+        let z = index / (nx * ny);
+        let get = |dx: usize, dy: usize, dz: usize| {
+            if dx > x || dy > y || dz > z {
+                T::zero()
+            } else {
+                data[index - dx - nx * (dy + ny * dz)]
+            }
+        };
+        match *self {
+            Predictor::LastValue => match index {
+                0 => T::zero(),
+                _ => data[index - 1],
+            },
+            Predictor::Plane => get(1, 0, 0).add(get(0, 1, 0)).sub(get(1, 1, 0)),
+            Predictor::Lorenzo => get(1, 0, 0)
+                .add(get(0, 1, 0))
+                .add(get(0, 0, 1))
+                .sub(get(1, 1, 0))
+                .sub(get(1, 0, 1))
+                .sub(get(0, 1, 1))
+                .add(get(1, 1, 1)),
+        }
+    }
+}
+
+/// Prediction residual struct to save the predictor and the shape of the grid
+///
+/// Each element of the grid is predicted from its preceding neighbours and replaced
+/// by the residual of the prediction (see [`Predictable`]).
+/// Smooth data results in small residuals, which can be coded efficiently.
+/// The residuals are written with the width of the elements in little-endian order.
+/// Trailing bytes, which do not form a complete element, are kept at the end.
+/// Empty data is passed through independent of the shape, e.g. for the last chunk of a stream.
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress_transformation::{PredictionResidual, Predictor, Shape};
+/// let model = PredictionResidual::<i32>::new(Predictor::Lorenzo, Shape::new(3, 2, 1));
+/// let residuals = model.encode(&[1, 2, 3, 2, 3, 4]).unwrap();
+/// assert_eq!(residuals, [1, 1, 1, 1, 0, 0]);
+/// assert_eq!(model.decode(&residuals).unwrap(), [1, 2, 3, 2, 3, 4]);
+/// ```
+#[derive(Debug)]
+pub struct PredictionResidual<T: Predictable> {
+    predictor: Predictor,
+    shape: Shape,
+    element: PhantomData<T>,
+}
+
+impl<T: Predictable> PredictionResidual<T> {
+    pub fn new(predictor: Predictor, shape: Shape) -> Self {
+        PredictionResidual {
+            predictor,
+            shape,
+            element: PhantomData,
+        }
+    }
+    fn check_shape(&self, len: usize) -> Result<(), TransformError> {
+        if len != self.shape.len() {
+            return Err(TransformError::ShapeMismatch(self.shape.len(), len));
+        }
+        Ok(())
+    }
+    /// Calculate the residuals of the typed grid
+    pub fn encode(&self, data: &[T]) -> Result<Vec<u64>, TransformError> {
+        self.check_shape(data.len())?;
+        Ok((0..data.len())
+            .map(|i| data[i].residual(self.predictor.predict(data, &self.shape, i)))
+            .collect())
+    }
+    /// Restore the typed grid from the residuals
+    pub fn decode(&self, residuals: &[u64]) -> Result<Vec<T>, TransformError> {
+        self.check_shape(residuals.len())?;
+        let mut result: Vec<T> = Vec::with_capacity(residuals.len());
+        for (i, r) in residuals.iter().enumerate() {
+            let prediction = self.predictor.predict(&result, &self.shape, i);
+            result.push(T::restore(*r, prediction));
+        }
+        Ok(result)
+    }
+}
+
+/// Implementation of the Transform trait for Prediction Residuals
+impl<T: Predictable> Transform for PredictionResidual<T> {
+    fn transform(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        if source.is_empty() {
+            return Ok(Vec::new());
+        }
+        let (elements, trailing) = to_elements::<T>(source);
+        debug!(
+            "Prediction residuals of {} elements with {:?}",
+            elements.len(),
+            self
+        );
+        let residuals = self.encode(&elements)?;
+        let size = T::DTYPE.size();
+        let mut result: Vec<u8> = Vec::with_capacity(source.len());
+        for r in residuals.iter() {
+            result.extend_from_slice(&r.to_le_bytes()[..size]);
+        }
+        result.extend_from_slice(trailing);
+        Ok(result)
+    }
+
+    fn reverse(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        if source.is_empty() {
+            return Ok(Vec::new());
+        }
+        let size = T::DTYPE.size();
+        let complete = source.len() - source.len() % size;
+        let residuals: Vec<u64> = source[..complete]
+            .chunks_exact(size)
+            .map(|c| {
+                let mut buffer = [0u8; 8];
+                buffer[..size].copy_from_slice(c);
+                u64::from_le_bytes(buffer)
+            })
+            .collect();
+        Ok(from_elements(
+            &self.decode(&residuals)?,
+            &source[complete..],
+        ))
+    }
+
+    fn accepts(&self, dtype: DataType) -> bool {
+        dtype == T::DTYPE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{random_roundtrip_with, roundtrip_with, transform_with};
+
+    const PREDICTORS: [Predictor; 3] = [Predictor::LastValue, Predictor::Plane, Predictor::Lorenzo];
+
+    #[test]
+    fn test_predictions() {
+        // 2x2x2 cube with f(x, y, z) = 1 + x + 2y + 4z
+        let data: Vec<i32> = (1..=8).collect();
+        let shape = Shape::new(2, 2, 2);
+        assert_eq!(Predictor::LastValue.predict(&data, &shape, 7), 7);
+        assert_eq!(Predictor::Plane.predict(&data, &shape, 7), 7 + 6 - 5);
+        assert_eq!(
+            Predictor::Lorenzo.predict(&data, &shape, 7),
+            7 + 6 + 4 - 5 - 3 - 2 + 1
+        );
+        assert_eq!(Predictor::Lorenzo.predict(&data, &shape, 0), 0);
+        assert_eq!(Predictor::Lorenzo.predict(&data, &shape, 4), 1);
+    }
+
+    #[test]
+    fn test_linear_grids_have_constant_residuals() {
+        let shape = Shape::new(4, 3, 2);
+        let data: Vec<f64> = (0..shape.len())
+            .map(|i| {
+                let (x, y, z) = (i % 4, (i / 4) % 3, i / 12);
+                0.5 * x as f64 + 2.0 * y as f64 - 1.0 * z as f64
+            })
+            .collect();
+        let model = PredictionResidual::<f64>::new(Predictor::Lorenzo, shape);
+        let residuals = model.encode(&data).unwrap();
+        // all elements not touching the lower boundaries are predicted exactly
+        for z in 1..2 {
+            for y in 1..3 {
+                for x in 1..4 {
+                    assert_eq!(residuals[x + 4 * (y + 3 * z)], 0);
+                }
+            }
+        }
+        assert_eq!(model.decode(&residuals).unwrap(), data);
+    }
+
+    #[test]
+    fn test_ordered_float_residuals() {
+        assert_eq!(1.0f32.residual(1.0), 0);
+        assert_eq!(0.0f32.residual(-0.0), 1);
+        assert_eq!((-0.0f32).residual(0.0), u32::MAX as u64);
+        let values = [
+            0.0f32,
+            -0.0,
+            f32::NAN,
+            f32::INFINITY,
+            f32::from_bits(1),
+            -3.5,
+        ];
+        for v in values.iter() {
+            for p in values.iter() {
+                let restored = f32::restore(v.residual(*p), *p);
+                assert_eq!(restored.to_bits(), v.to_bits());
+            }
+        }
+    }
+
+    #[test]
+    fn test_shape_mismatch() {
+        let model = PredictionResidual::<u16>::new(Predictor::Plane, Shape::new(2, 2, 1));
+        assert!(model.encode(&[1, 2, 3]).is_err());
+        let mut model = PredictionResidual::<u16>::new(Predictor::Plane, Shape::new(2, 2, 1));
+        assert!(model.transform(&[1, 2, 3, 4, 5, 6]).is_err());
+    }
+
+    #[test]
+    fn test_short_roundtrip() {
+        for predictor in PREDICTORS.iter() {
+            let mut model = PredictionResidual::<u8>::new(*predictor, Shape::new(2, 2, 1));
+            transform_with(&mut model, &[], &[]);
+            roundtrip_with(&mut model, &[]);
+            for shape in [
+                Shape::new(1, 1, 1),
+                Shape::new(2, 1, 1),
+                Shape::new(1, 2, 1),
+            ]
+            .iter()
+            {
+                let mut model = PredictionResidual::<u8>::new(*predictor, *shape);
+                for a in 0..=255u8 {
+                    roundtrip_with(&mut model, &[a, 255 - a][..shape.len()]);
+                }
+            }
+            let mut model = PredictionResidual::<u16>::new(*predictor, Shape::new(1, 1, 1));
+            roundtrip_with(&mut model, &[0xff, 0x80]);
+        }
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for predictor in PREDICTORS.iter() {
+            let mut model = PredictionResidual::<u8>::new(*predictor, Shape::new(5, 3, 1));
+            roundtrip_with(&mut model, "compressioncode".as_bytes());
+            let mut model = PredictionResidual::<f32>::new(*predictor, Shape::new(2, 2, 2));
+            roundtrip_with(&mut model, &[7u8; 35]);
+        }
+    }
+
+    #[test]
+    fn test_random_roundtrip() {
+        let shape = Shape::new(25, 20, 5);
+        for predictor in PREDICTORS.iter() {
+            random_roundtrip_with(
+                || PredictionResidual::<u8>::new(*predictor, shape),
+                10,
+                2_500,
+            );
+            random_roundtrip_with(
+                || PredictionResidual::<i16>::new(*predictor, shape),
+                10,
+                5_001,
+            );
+            random_roundtrip_with(
+                || PredictionResidual::<u32>::new(*predictor, shape),
+                10,
+                10_000,
+            );
+            random_roundtrip_with(
+                || PredictionResidual::<i64>::new(*predictor, shape),
+                10,
+                20_003,
+            );
+            random_roundtrip_with(
+                || PredictionResidual::<f32>::new(*predictor, shape),
+                10,
+                10_000,
+            );
+            random_roundtrip_with(
+                || PredictionResidual::<f64>::new(*predictor, shape),
+                10,
+                20_007,
+            );
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! Property-based tests of all transformations
+//!
+//! Every transformation must reverse its own output and must not panic on
+//! arbitrary input for the reversal. The generators are seeded with
+//! [`rscompress_testing::seed`] for reproducibility and shrink failing inputs
+//! to a minimal example.
+//! Failing inputs are persisted in `proptest-regressions/properties.txt` and
+//! are always tested first.
+use crate::{
+    BitShuffle, BurrowWheeler, ByteShuffle, Chain, Delta, Lz4Block, Lz4BlockSize, Lz4Frame, Lz77,
+    MoveToFront, PackBits, Policy, PredictionResidual, Predictor, RunLength, Shape,
+    ThresholdRunLength, Transform, VarintRunLength, WeightedFrequencyCount, XorPredictor,
+    XorResidual, ZeroRunLength,
+};
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+/// Maximum length of arbitrary inputs
+const MAX_LENGTH: usize = 4_096;
+
+/// Transformations independent of the input length
+fn models() -> Vec<(&'static str, Box<dyn Transform>)> {
+    vec![
+        ("Burrow-Wheeler", Box::new(BurrowWheeler::new())),
+        (
+            "Chain",
+            Box::new(Chain::with_stages(vec![
+                Box::new(BurrowWheeler::new()),
+                Box::new(MoveToFront::new()),
+                Box::new(ZeroRunLength::new()),
+            ])),
+        ),
+        ("Delta<u8>", Box::new(Delta::<u8>::new())),
+        (
+            "Delta<i16>",
+            Box::new(Delta::<i16>::with_order_and_stride(2, 1)),
+        ),
+        (
+            "Delta<u32>",
+            Box::new(Delta::<u32>::with_order_and_stride(1, 3)),
+        ),
+        ("Delta<u64>", Box::new(Delta::<u64>::new())),
+        ("LZ4 Block", Box::new(Lz4Block::new())),
+        ("LZ4 Frame", Box::new(Lz4Frame::new())),
+        (
+            "LZ4 Frame (64KB)",
+            Box::new(Lz4Frame::with_options(
+                Lz4BlockSize::Max64KB,
+                true,
+                true,
+                true,
+            )),
+        ),
+        ("LZ77", Box::new(Lz77::new())),
+        (
+            "LZ77 (greedy)",
+            Box::new(Lz77::with_window_and_lazy(64, false)),
+        ),
+        ("Move-To-Front", Box::new(MoveToFront::new())),
+        (
+            "Move-To-Front (sticky)",
+            Box::new(MoveToFront::with_policy(Policy::Sticky)),
+        ),
+        ("PackBits", Box::new(PackBits::new())),
+        ("Run-Length", Box::new(RunLength::new())),
+        ("Byte-Shuffle", Box::new(ByteShuffle::new(4))),
+        ("Bit-Shuffle", Box::new(BitShuffle::new(3))),
+        ("Threshold-Run-Length", Box::new(ThresholdRunLength::new())),
+        ("Varint-Run-Length", Box::new(VarintRunLength::new())),
+        (
+            "Weighted-Frequency-Count",
+            Box::new(WeightedFrequencyCount::new()),
+        ),
+        ("XOR<f32>", Box::new(XorResidual::<f32>::new())),
+        (
+            "XOR<f64>",
+            Box::new(XorResidual::<f64>::with_predictor(XorPredictor::Fcm)),
+        ),
+        ("Zero-Run", Box::new(ZeroRunLength::new())),
+    ]
+}
+
+/// Transformations with a shape matching the input length
+fn shaped_models(len: usize) -> Vec<(&'static str, Box<dyn Transform>)> {
+    vec![
+        (
+            "Prediction-Residual<u8>",
+            Box::new(PredictionResidual::<u8>::new(
+                Predictor::Lorenzo,
+                Shape::new(len, 1, 1),
+            )),
+        ),
+        (
+            "Prediction-Residual<u16>",
+            Box::new(PredictionResidual::<u16>::new(
+                Predictor::Plane,
+                Shape::new(1, len / 2, 1),
+            )),
+        ),
+    ]
+}
+
+/// Arbitrary bytes
+fn arbitrary() -> impl Strategy<Value = Vec<u8>> {
+    vec(any::<u8>(), 0..MAX_LENGTH)
+}
+
+/// Bytes of a small alphabet with a skewed distribution
+fn skewed() -> impl Strategy<Value = Vec<u8>> {
+    vec(any::<u8>(), 1..5).prop_flat_map(|alphabet| {
+        let last = alphabet.len() - 1;
+        vec(0usize..64, 0..MAX_LENGTH).prop_map(move |ix| {
+            ix.iter()
+                .map(|i| alphabet[(i.trailing_zeros() as usize).min(last)])
+                .collect()
+        })
+    })
+}
+
+/// Long runs of bytes, crossing the maximum run lengths of the transformations
+fn runs() -> impl Strategy<Value = Vec<u8>> {
+    vec((any::<u8>(), 1usize..600), 0..16).prop_map(|runs| {
+        runs.iter()
+            .flat_map(|(byte, len)| vec![*byte; *len])
+            .collect()
+    })
+}
+
+/// The same byte around the boundaries of blocks of 64KB
+fn same() -> impl Strategy<Value = Vec<u8>> {
+    (any::<u8>(), 65_530usize..65_542).prop_map(|(byte, len)| vec![byte; len])
+}
+
+/// Check that all transformations reverse their own output
+fn check_roundtrip(input: &[u8]) -> Result<(), TestCaseError> {
+    let mut models = models();
+    models.extend(shaped_models(input.len()));
+    for (name, model) in models.iter_mut() {
+        let tmp = model
+            .transform(input)
+            .map_err(|e| TestCaseError::fail(format!("{}: {}", name, e)))?;
+        let result = model
+            .reverse(&tmp)
+            .map_err(|e| TestCaseError::fail(format!("{}: {}", name, e)))?;
+        prop_assert_eq!(&result, &input, "{} failed", name);
+    }
+    Ok(())
+}
+
+fn config(cases: u32) -> ProptestConfig {
+    ProptestConfig {
+        cases,
+        ..rscompress_testing::config()
+    }
+}
+
+proptest! {
+    #![proptest_config(config(64))]
+
+    #[test]
+    fn roundtrip_arbitrary(input in arbitrary()) {
+        check_roundtrip(&input)?;
+    }
+
+    #[test]
+    fn roundtrip_skewed(input in skewed()) {
+        check_roundtrip(&input)?;
+    }
+
+    #[test]
+    fn roundtrip_runs(input in runs()) {
+        check_roundtrip(&input)?;
+    }
+
+    #[test]
+    fn reverse_arbitrary(input in arbitrary()) {
+        let mut models = models();
+        models.extend(shaped_models(input.len()));
+        for (_, model) in models.iter_mut() {
+            let _ = model.reverse(&input);
+        }
+    }
+}
+
+proptest! {
+    #![proptest_config(config(4))]
+
+    #[test]
+    fn roundtrip_same(input in same()) {
+        check_roundtrip(&input)?;
+    }
+}
@@ -12,6 +12,7 @@ const RUN_BYTE_CODE: u8 = 0;
 pub struct RunLength {
     current: Option<u8>,
     reverse_started: bool,
+    initial: Option<u8>,
 }
 
 impl RunLength {
@@ -19,6 +20,7 @@ impl RunLength {
         RunLength {
             current: None,
             reverse_started: false,
+            initial: None,
         }
     }
 }
@@ -32,10 +34,25 @@ impl Default for RunLength {
 /// Implementation of the Transform trait for Run-Length
 impl Transform for RunLength {
     fn transform(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
-        if source.is_empty() {
-            return Err(TransformError::EmptyBufferError);
-        }
         let mut result: Vec<u8> = Vec::with_capacity(source.len());
+        self.transform_into(source, &mut result)?;
+        Ok(result)
+    }
+
+    fn reverse(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let mut result: Vec<u8> = Vec::with_capacity(source.len());
+        self.reverse_into(source, &mut result)?;
+        Ok(result)
+    }
+
+    fn transform_into(
+        &mut self,
+        source: &[u8],
+        result: &mut Vec<u8>,
+    ) -> Result<(), TransformError> {
+        self.initial = self.current;
+        result.clear();
+        result.reserve(source.len());
         for byte in source.iter() {
             info!("Transform: {} | {:?}", byte, self);
             if self.current.is_some() && self.current.unwrap() == *byte {
@@ -49,40 +66,53 @@ impl Transform for RunLength {
             }
         }
         self.reverse_started = false;
-        Ok(result)
+        Ok(())
     }
 
-    fn reverse(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
-        if source.is_empty() {
-            return Err(TransformError::EmptyBufferError);
-        }
-        let mut result: Vec<u8> = Vec::with_capacity(source.len());
+    fn reverse_into(&mut self, source: &[u8], result: &mut Vec<u8>) -> Result<(), TransformError> {
+        result.clear();
+        result.reserve(source.len());
         for byte in source.iter() {
             info!("Reverse: {} | {:?}", byte, self);
-            if self.current.is_some() && *byte == RUN_BYTE_CODE && self.reverse_started {
-                result.push(self.current.unwrap());
-                self.reverse_started = true;
-            } else if self.current.is_some()
-                && *byte == self.current.unwrap()
-                && self.reverse_started
-            {
-                result.push(RUN_BYTE_CODE);
-                self.current = Some(RUN_BYTE_CODE);
-                self.reverse_started = true;
-            } else {
-                result.push(*byte);
-                self.current = Some(*byte);
-                self.reverse_started = true;
+            match self.current {
+                Some(current) if *byte == RUN_BYTE_CODE && self.reverse_started => {
+                    result.push(current);
+                }
+                Some(current) if *byte == current && self.reverse_started => {
+                    result.push(RUN_BYTE_CODE);
+                    self.current = Some(RUN_BYTE_CODE);
+                }
+                _ => {
+                    result.push(*byte);
+                    self.current = Some(*byte);
+                }
             }
+            self.reverse_started = true;
         }
-        Ok(result)
+        Ok(())
+    }
+
+    /// The metadata is the current byte at the start of the last transformation (if any)
+    fn export_metadata(&self) -> Vec<u8> {
+        self.initial.into_iter().collect()
+    }
+    fn import_metadata(&mut self, metadata: &[u8]) -> Result<(), TransformError> {
+        if metadata.len() > 1 {
+            return Err(TransformError::InvalidMetadata);
+        }
+        self.current = metadata.first().copied();
+        self.reverse_started = self.current.is_some();
+        Ok(())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::tests::{random_roundtrip, reverse, roundtrip, transform};
+    use crate::tests::{
+        metadata_roundtrip_with, random_roundtrip, reverse, roundtrip, roundtrip_into,
+        short_roundtrip_with, transform,
+    };
 
     #[test]
     fn test_easy_transforms() {
@@ -133,6 +163,30 @@ mod tests {
         roundtrip::<RunLength>(&[8, 1, 5, 8]);
     }
 
+    #[test]
+    fn test_roundtrip_into() {
+        roundtrip_into::<RunLength>(&[8, 2, 2, 2, 24, 32, 32, 1, 24]);
+        roundtrip_into::<RunLength>(&[0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_metadata() {
+        let chunks: [&[u8]; 3] = [&[1, 2, 2], &[2, 2, 0, 0, 3], &[3, 0, 3]];
+        metadata_roundtrip_with(RunLength::new, &chunks);
+        let mut model = RunLength::new();
+        assert!(model.export_metadata().is_empty());
+        model.transform(&[1, 2]).unwrap();
+        model.transform(&[3]).unwrap();
+        assert_eq!(model.export_metadata(), [2]);
+        assert!(model.import_metadata(&[1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_short_roundtrip() {
+        short_roundtrip_with(RunLength::new);
+        transform::<RunLength>(&[], &[]);
+    }
+
     #[test]
     fn test_random_roundtrip() {
         random_roundtrip::<RunLength>(100, 10_000);
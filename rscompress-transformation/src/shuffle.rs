@@ -0,0 +1,237 @@
+//! Shuffle Transformations
+//!
+//! Implementation of the byte and bit shuffle filters known from
+//! [Blosc](https://www.blosc.org/) and [HDF5](https://www.hdfgroup.org/).
+use crate::{DataType, Transform, TransformError};
+use log::debug;
+
+const DEFAULT_ELEMENT_SIZE: usize = 4;
+
+/// Byte shuffle struct to save the size of the elements
+///
+/// The bytes of an array of elements with `element_size` bytes each are regrouped,
+/// such that the first bytes of all elements come first, followed by
+/// all second bytes and so on. Bytes of numerical data with similar magnitude
+/// (e.g. the exponents of floating point numbers) are placed next to each other,
+/// which is beneficial for the following coding stage.
+/// Trailing bytes, which do not form a complete element, are kept at the end.
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress_transformation::{ByteShuffle, Transform};
+/// let mut model = ByteShuffle::new(2);
+/// let transformed = model.transform(&[1, 2, 3, 4, 5, 6, 7]).unwrap();
+/// assert_eq!(transformed, [1, 3, 5, 2, 4, 6, 7]);
+/// ```
+#[derive(Debug)]
+pub struct ByteShuffle {
+    element_size: usize,
+}
+
+impl ByteShuffle {
+    /// Generate byte shuffle for elements of `element_size` bytes (at least 1)
+    pub fn new(element_size: usize) -> Self {
+        ByteShuffle {
+            element_size: element_size.max(1),
+        }
+    }
+}
+
+/// The default byte shuffle works on four byte elements e.g. `f32` or `u32`
+impl Default for ByteShuffle {
+    fn default() -> Self {
+        Self::new(DEFAULT_ELEMENT_SIZE)
+    }
+}
+
+/// Implementation of the Transform trait for Byte Shuffle
+impl Transform for ByteShuffle {
+    fn transform(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let k = self.element_size;
+        let n = source.len() / k;
+        let mut result: Vec<u8> = Vec::with_capacity(source.len());
+        for b in 0..k {
+            result.extend((0..n).map(|e| source[e * k + b]));
+        }
+        result.extend_from_slice(&source[n * k..]);
+        debug!("Byte shuffled {} elements of size {}", n, k);
+        Ok(result)
+    }
+
+    fn reverse(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let k = self.element_size;
+        let n = source.len() / k;
+        let mut result: Vec<u8> = vec![0u8; source.len()];
+        for b in 0..k {
+            for e in 0..n {
+                result[e * k + b] = source[b * n + e];
+            }
+        }
+        result[n * k..].copy_from_slice(&source[n * k..]);
+        Ok(result)
+    }
+
+    fn accepts(&self, dtype: DataType) -> bool {
+        dtype.size() == self.element_size
+    }
+}
+
+/// Bit shuffle struct to save the size of the elements
+///
+/// Like [`ByteShuffle`], but the single bits are regrouped instead of bytes.
+/// The output starts with the lowest bit of all elements, followed by the second lowest
+/// bit of all elements and so on. Bits are numbered from the least significant bit
+/// of the first byte (little-endian) to the most significant bit of the last byte.
+///
+/// The bits are transposed in groups of eight elements.
+/// Elements which do not fill a complete group as well as trailing bytes,
+/// which do not form a complete element, are kept at the end.
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress_transformation::{BitShuffle, Transform};
+/// let mut model = BitShuffle::new(1);
+/// let transformed = model.transform(&[1, 1, 1, 1, 0, 0, 0, 1]).unwrap();
+/// assert_eq!(transformed, [0b1000_1111, 0, 0, 0, 0, 0, 0, 0]);
+/// ```
+#[derive(Debug)]
+pub struct BitShuffle {
+    element_size: usize,
+}
+
+impl BitShuffle {
+    /// Generate bit shuffle for elements of `element_size` bytes (at least 1)
+    pub fn new(element_size: usize) -> Self {
+        BitShuffle {
+            element_size: element_size.max(1),
+        }
+    }
+}
+
+/// The default bit shuffle works on four byte elements e.g. `f32` or `u32`
+impl Default for BitShuffle {
+    fn default() -> Self {
+        Self::new(DEFAULT_ELEMENT_SIZE)
+    }
+}
+
+/// Implementation of the Transform trait for Bit Shuffle
+impl Transform for BitShuffle {
+    fn transform(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let k = self.element_size;
+        let groups = source.len() / k / 8;
+        let mut result: Vec<u8> = vec![0u8; source.len()];
+        for bit in 0..k * 8 {
+            let (b, i) = (bit / 8, bit % 8);
+            for g in 0..groups {
+                let mut packed = 0u8;
+                for l in 0..8 {
+                    packed |= ((source[(g * 8 + l) * k + b] >> i) & 1) << l;
+                }
+                result[bit * groups + g] = packed;
+            }
+        }
+        let shuffled = groups * 8 * k;
+        result[shuffled..].copy_from_slice(&source[shuffled..]);
+        debug!("Bit shuffled {} groups of elements of size {}", groups, k);
+        Ok(result)
+    }
+
+    fn reverse(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let k = self.element_size;
+        let groups = source.len() / k / 8;
+        let mut result: Vec<u8> = vec![0u8; source.len()];
+        for bit in 0..k * 8 {
+            let (b, i) = (bit / 8, bit % 8);
+            for g in 0..groups {
+                let packed = source[bit * groups + g];
+                for l in 0..8 {
+                    result[(g * 8 + l) * k + b] |= ((packed >> l) & 1) << i;
+                }
+            }
+        }
+        let shuffled = groups * 8 * k;
+        result[shuffled..].copy_from_slice(&source[shuffled..]);
+        Ok(result)
+    }
+
+    fn accepts(&self, dtype: DataType) -> bool {
+        dtype.size() == self.element_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{
+        random_roundtrip, random_roundtrip_with, roundtrip_with, short_roundtrip_with,
+        transform_with,
+    };
+
+    #[test]
+    fn test_byte_shuffle_transforms() {
+        transform_with(
+            &mut ByteShuffle::new(2),
+            &[1, 2, 3, 4, 5, 6],
+            &[1, 3, 5, 2, 4, 6],
+        );
+        transform_with(
+            &mut ByteShuffle::new(3),
+            &[1, 2, 3, 4, 5, 6, 7, 8],
+            &[1, 4, 2, 5, 3, 6, 7, 8],
+        );
+        transform_with(&mut ByteShuffle::new(4), &[1, 2, 3], &[1, 2, 3]);
+        transform_with(&mut ByteShuffle::new(1), &[1, 2, 3], &[1, 2, 3]);
+    }
+
+    #[test]
+    fn test_bit_shuffle_transforms() {
+        // the identity matrix is its own transpose
+        let input: Vec<u8> = (0..8).map(|x| 1 << x).chain(Some(42)).collect();
+        transform_with(&mut BitShuffle::new(1), &input, &input);
+        // two byte elements with only the highest bit set
+        let input = [0, 128].repeat(8);
+        let mut expected = vec![0u8; 15];
+        expected.push(255);
+        transform_with(&mut BitShuffle::new(2), &input, &expected);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for k in 1..10 {
+            roundtrip_with(&mut ByteShuffle::new(k), "compressioncode".as_bytes());
+            roundtrip_with(&mut BitShuffle::new(k), "compressioncode".as_bytes());
+            roundtrip_with(&mut BitShuffle::new(k), &[7u8; 1_000]);
+        }
+    }
+
+    #[test]
+    fn test_short_roundtrip() {
+        for k in 1..4 {
+            short_roundtrip_with(|| ByteShuffle::new(k));
+            short_roundtrip_with(|| BitShuffle::new(k));
+        }
+        transform_with(&mut ByteShuffle::new(2), &[], &[]);
+    }
+
+    #[test]
+    fn test_random_roundtrip() {
+        random_roundtrip::<ByteShuffle>(10, 10_000);
+        random_roundtrip::<BitShuffle>(10, 10_000);
+        for k in [1, 2, 3, 8, 16].iter() {
+            random_roundtrip_with(|| ByteShuffle::new(*k), 10, 10_001);
+            random_roundtrip_with(|| BitShuffle::new(*k), 10, 10_001);
+        }
+    }
+
+    #[test]
+    fn test_accepts() {
+        assert!(ByteShuffle::new(8).accepts(DataType::U64));
+        assert!(ByteShuffle::new(8).accepts(DataType::F64));
+        assert!(!ByteShuffle::new(8).accepts(DataType::F32));
+        assert!(BitShuffle::new(4).accepts(DataType::I32));
+        assert!(!BitShuffle::new(4).accepts(DataType::I64));
+    }
+}
@@ -0,0 +1,149 @@
+//! Threshold Run-length Transformation
+//!
+//! Implementation of the run-length encoding applied by
+//! [bzip2](https://en.wikipedia.org/wiki/Bzip2) before the Burrow-Wheeler Transformation (RLE-4).
+use crate::{Transform, TransformError};
+use log::debug;
+
+const DEFAULT_THRESHOLD: usize = 4;
+const MAX_COUNT: usize = u8::MAX as usize;
+
+/// Run-length struct escaping runs after `threshold` repetitions
+///
+/// Bytes are copied as they are, until the same byte was seen `threshold` times in a row.
+/// Then a single count byte follows with the number of additional repetitions (`0..=255`).
+/// Longer runs are split into several runs.
+/// Runs shorter than the threshold do not cost anything, while longer runs are reduced
+/// to `threshold + 1` bytes. The worst case expansion is one byte per `threshold` bytes.
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress_transformation::{ThresholdRunLength, Transform};
+/// let mut model = ThresholdRunLength::new();
+/// let transformed = model.transform(&[7, 7, 7, 7, 7, 7, 1]).unwrap();
+/// assert_eq!(transformed, [7, 7, 7, 7, 2, 1]);
+/// ```
+#[derive(Debug)]
+pub struct ThresholdRunLength {
+    threshold: usize,
+}
+
+impl ThresholdRunLength {
+    pub fn new() -> Self {
+        Self::with_threshold(DEFAULT_THRESHOLD)
+    }
+    /// Generate run-length encoding escaping after `threshold` repetitions (at least 1)
+    pub fn with_threshold(threshold: usize) -> Self {
+        ThresholdRunLength {
+            threshold: threshold.max(1),
+        }
+    }
+}
+
+impl Default for ThresholdRunLength {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Implementation of the Transform trait for Threshold Run-Length
+impl Transform for ThresholdRunLength {
+    fn transform(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let mut result: Vec<u8> = Vec::with_capacity(source.len());
+        let mut i = 0;
+        while i < source.len() {
+            let run = source[i..]
+                .iter()
+                .take(self.threshold.saturating_add(MAX_COUNT))
+                .take_while(|&&b| b == source[i])
+                .count();
+            result.resize(result.len() + run.min(self.threshold), source[i]);
+            if run >= self.threshold {
+                result.push((run - self.threshold) as u8);
+            }
+            i += run;
+        }
+        debug!(
+            "Threshold run-length encoded {} bytes into {}",
+            source.len(),
+            result.len()
+        );
+        Ok(result)
+    }
+
+    fn reverse(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let mut result: Vec<u8> = Vec::with_capacity(source.len());
+        let mut last: Option<u8> = None;
+        let mut seen = 0;
+        let mut bytes = source.iter();
+        while let Some(byte) = bytes.next() {
+            if last == Some(*byte) {
+                seen += 1;
+            } else {
+                last = Some(*byte);
+                seen = 1;
+            }
+            result.push(*byte);
+            if seen == self.threshold {
+                let count = *bytes.next().ok_or(TransformError::TruncatedBuffer)?;
+                result.resize(result.len() + count as usize, *byte);
+                last = None;
+                seen = 0;
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{
+        random_roundtrip, random_roundtrip_with, reverse, roundtrip, short_roundtrip_with,
+        transform,
+    };
+
+    #[test]
+    fn test_easy_transforms() {
+        transform::<ThresholdRunLength>(&[8, 2, 2, 2, 24], &[8, 2, 2, 2, 24]);
+        transform::<ThresholdRunLength>(&[2, 2, 2, 2, 24], &[2, 2, 2, 2, 0, 24]);
+        transform::<ThresholdRunLength>(&[3; 260], &[3, 3, 3, 3, 255, 3]);
+    }
+
+    #[test]
+    fn test_easy_reverses() {
+        reverse::<ThresholdRunLength>(&[2, 2, 2, 2, 0, 24], &[2, 2, 2, 2, 24]);
+        reverse::<ThresholdRunLength>(&[3, 3, 3, 3, 255, 3], &[3; 260]);
+        reverse::<ThresholdRunLength>(&[3, 3, 3, 3, 1, 3, 3, 3, 3, 0], &[3; 9]);
+    }
+
+    #[test]
+    fn test_truncated_reverses() {
+        let mut model = ThresholdRunLength::new();
+        assert!(model.reverse(&[1, 1, 1, 1]).is_err());
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        roundtrip::<ThresholdRunLength>(&[8, 2, 2, 2, 24, 32, 32, 1, 24]);
+        roundtrip::<ThresholdRunLength>(&[8; 1_000]);
+        roundtrip::<ThresholdRunLength>(&[8, 8, 8, 8, 0, 0, 0, 0, 0]);
+        roundtrip::<ThresholdRunLength>("compressioncode".as_bytes());
+    }
+
+    #[test]
+    fn test_short_roundtrip() {
+        short_roundtrip_with(ThresholdRunLength::new);
+        short_roundtrip_with(|| ThresholdRunLength::with_threshold(1));
+        short_roundtrip_with(|| ThresholdRunLength::with_threshold(usize::MAX));
+        transform::<ThresholdRunLength>(&[], &[]);
+    }
+
+    #[test]
+    fn test_random_roundtrip() {
+        random_roundtrip::<ThresholdRunLength>(100, 10_000);
+        random_roundtrip_with(|| ThresholdRunLength::with_threshold(1), 100, 10_000);
+        random_roundtrip_with(|| ThresholdRunLength::with_threshold(2), 100, 10_000);
+    }
+}
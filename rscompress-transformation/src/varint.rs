@@ -0,0 +1,63 @@
+//! Variable-length integers
+//!
+//! Unsigned integers are written in [LEB128](https://en.wikipedia.org/wiki/LEB128) format
+//! i.e. seven bits per byte, least significant group first.
+//! The highest bit of each byte signals if another byte follows.
+use crate::TransformError;
+
+/// Append `value` as variable-length integer
+pub(crate) fn push_varint(result: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        result.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    result.push(value as u8);
+}
+
+/// Read a variable-length integer starting at `pos` and advance `pos`
+pub(crate) fn read_varint(source: &[u8], pos: &mut usize) -> Result<usize, TransformError> {
+    let mut value = 0usize;
+    let mut shift = 0u32;
+    loop {
+        let byte = *source.get(*pos).ok_or(TransformError::TruncatedBuffer)?;
+        *pos += 1;
+        let bits = (byte & 0x7f) as usize;
+        if shift >= usize::BITS || (bits << shift) >> shift != bits {
+            return Err(TransformError::InvalidRunLength);
+        }
+        value |= bits << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_varint_roundtrip() {
+        for value in [0, 1, 127, 128, 300, 16_384, usize::MAX].iter() {
+            let mut buffer = Vec::new();
+            push_varint(&mut buffer, *value);
+            let mut pos = 0;
+            assert_eq!(read_varint(&buffer, &mut pos).unwrap(), *value);
+            assert_eq!(pos, buffer.len());
+        }
+    }
+
+    #[test]
+    fn test_varint_encoding() {
+        let mut buffer = Vec::new();
+        push_varint(&mut buffer, 300);
+        assert_eq!(buffer, [0xac, 0x02]);
+    }
+
+    #[test]
+    fn test_varint_errors() {
+        assert!(read_varint(&[0x80], &mut 0).is_err());
+        assert!(read_varint(&[0xff; 11], &mut 0).is_err());
+    }
+}
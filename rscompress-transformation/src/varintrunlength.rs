@@ -0,0 +1,142 @@
+//! Varint Run-length Transformation
+//!
+//! Implementation of a run-length encoding writing each run as
+//! byte followed by its length as variable-length integer.
+use crate::varint::{push_varint, read_varint};
+use crate::{Transform, TransformError};
+use log::debug;
+
+/// Maximal length of a run, longer runs are split into several runs
+pub const MAX_VARINT_RUN: usize = 1 << 16;
+
+/// Run-length struct writing pairs of byte and count
+///
+/// Each run of `n` identical bytes is written as the byte followed by `n - 1`
+/// as [LEB128](https://en.wikipedia.org/wiki/LEB128) variable-length integer.
+/// Runs of up to [`MAX_VARINT_RUN`] bytes are therefore represented by a few bytes,
+/// but data without runs doubles in size. Longer runs are split, such that reversing
+/// arbitrary input expands the data at most `MAX_VARINT_RUN / 4` times.
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress_transformation::{Transform, VarintRunLength};
+/// let mut model = VarintRunLength::new();
+/// let transformed = model.transform(&[7; 300]).unwrap();
+/// assert_eq!(transformed, [7, 0xab, 0x02]);
+/// ```
+#[derive(Debug)]
+pub struct VarintRunLength {}
+
+impl VarintRunLength {
+    pub fn new() -> Self {
+        VarintRunLength {}
+    }
+}
+
+impl Default for VarintRunLength {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Implementation of the Transform trait for Varint Run-Length
+impl Transform for VarintRunLength {
+    fn transform(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let mut result: Vec<u8> = Vec::with_capacity(source.len());
+        let mut i = 0;
+        while i < source.len() {
+            let run = source[i..]
+                .iter()
+                .take(MAX_VARINT_RUN)
+                .take_while(|&&b| b == source[i])
+                .count();
+            result.push(source[i]);
+            push_varint(&mut result, run - 1);
+            i += run;
+        }
+        debug!(
+            "Varint run-length encoded {} bytes into {}",
+            source.len(),
+            result.len()
+        );
+        Ok(result)
+    }
+
+    fn reverse(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let mut result: Vec<u8> = Vec::with_capacity(source.len());
+        let mut i = 0;
+        while i < source.len() {
+            let byte = source[i];
+            i += 1;
+            let run = read_varint(source, &mut i)?
+                .checked_add(1)
+                .filter(|run| *run <= MAX_VARINT_RUN)
+                .ok_or(TransformError::InvalidRunLength)?;
+            result.resize(result.len() + run, byte);
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{random_roundtrip, reverse, roundtrip, short_roundtrip_with, transform};
+
+    #[test]
+    fn test_easy_transforms() {
+        transform::<VarintRunLength>(&[8, 2, 2, 2, 24], &[8, 0, 2, 2, 24, 0]);
+        transform::<VarintRunLength>(&[0; 129], &[0, 0x80, 0x01]);
+    }
+
+    #[test]
+    fn test_easy_reverses() {
+        reverse::<VarintRunLength>(&[8, 0, 2, 2, 24, 0], &[8, 2, 2, 2, 24]);
+        reverse::<VarintRunLength>(&[1, 0x80, 0x01], &[1; 129]);
+    }
+
+    #[test]
+    fn test_truncated_reverses() {
+        let mut model = VarintRunLength::new();
+        assert!(model.reverse(&[8]).is_err());
+        assert!(model.reverse(&[8, 0x80]).is_err());
+    }
+
+    #[test]
+    fn test_run_limit() {
+        transform::<VarintRunLength>(&[3; MAX_VARINT_RUN + 2], &[3, 0xff, 0xff, 0x03, 3, 1]);
+        reverse::<VarintRunLength>(&[3, 0xff, 0xff, 0x03], &[3; MAX_VARINT_RUN]);
+        let mut model = VarintRunLength::new();
+        assert!(matches!(
+            model.reverse(&[3, 0x80, 0x80, 0x04]),
+            Err(TransformError::InvalidRunLength)
+        ));
+        let huge = [
+            1, 0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01,
+        ];
+        assert!(matches!(
+            model.reverse(&huge),
+            Err(TransformError::InvalidRunLength)
+        ));
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        roundtrip::<VarintRunLength>(&[8, 2, 2, 2, 24, 32, 32, 1, 24]);
+        roundtrip::<VarintRunLength>(&[8; 100_000]);
+        roundtrip::<VarintRunLength>("compressioncode".as_bytes());
+    }
+
+    #[test]
+    fn test_short_roundtrip() {
+        short_roundtrip_with(VarintRunLength::new);
+        transform::<VarintRunLength>(&[], &[]);
+    }
+
+    #[test]
+    fn test_random_roundtrip() {
+        random_roundtrip::<VarintRunLength>(100, 10_000);
+        random_roundtrip::<VarintRunLength>(100, 10_000);
+    }
+}
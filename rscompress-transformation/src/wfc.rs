@@ -0,0 +1,222 @@
+//! Weighted Frequency Count Transformation
+//!
+//! Implementation of the Weighted Frequency Count (WFC) transformation as
+//! described by Deorowicz in "Second step algorithms in the Burrows-Wheeler compression algorithm".
+use crate::{Transform, TransformError};
+use log::debug;
+use std::collections::VecDeque;
+
+const ALPHABET_SIZE: u8 = 255; // + 1 number of elements
+const DEFAULT_WINDOW: usize = 16;
+
+/// Ranking of symbols together with the recent history
+#[derive(Debug)]
+struct Ranking {
+    table: Vec<u8>,
+    history: VecDeque<u8>,
+}
+
+impl Ranking {
+    fn new(window: usize) -> Self {
+        Ranking {
+            table: (0u8..=ALPHABET_SIZE).collect(),
+            history: VecDeque::with_capacity(window + 1),
+        }
+    }
+    fn update(&mut self, symbol: u8, window: usize) {
+        self.history.push_front(symbol);
+        self.history.truncate(window);
+        let mut scores = [0usize; ALPHABET_SIZE as usize + 1];
+        for (distance, s) in self.history.iter().enumerate() {
+            // recent occurrences are weighted higher
+            scores[*s as usize] += window - distance;
+        }
+        // stable sort keeps the previous order for symbols with equal scores
+        self.table
+            .sort_by(|a, b| scores[*b as usize].cmp(&scores[*a as usize]));
+    }
+    fn copy_from(&mut self, other: &Ranking) {
+        self.table.copy_from_slice(&other.table);
+        self.history.clone_from(&other.history);
+    }
+}
+
+/// Weighted Frequency Count struct to save the ranking and the recent history
+///
+/// Like Move-To-Front each symbol is replaced by its rank in a table of all symbols.
+/// The table is not updated by moving a single symbol, but by sorting all symbols
+/// by the weighted sum of their occurrences in the last `window` symbols.
+/// Recent occurrences are weighted higher than older ones.
+/// Symbols with the same score keep their relative order.
+///
+/// Consecutive calls are treated as one continuous stream with separate states
+/// for the forward and reverse direction (see [`crate::Continuity::Stream`]).
+/// The metadata of the transformation is the table and the history at the start
+/// of the last call, such that a single chunk can be reversed by a new model.
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress_transformation::WeightedFrequencyCount;
+/// let wfc = WeightedFrequencyCount::with_window(32);
+/// ```
+#[derive(Debug)]
+pub struct WeightedFrequencyCount {
+    forward: Ranking,
+    backward: Ranking,
+    initial: Ranking,
+    window: usize,
+}
+
+impl WeightedFrequencyCount {
+    pub fn new() -> Self {
+        Self::with_window(DEFAULT_WINDOW)
+    }
+    pub fn with_window(window: usize) -> Self {
+        WeightedFrequencyCount {
+            forward: Ranking::new(window),
+            backward: Ranking::new(window),
+            initial: Ranking::new(window),
+            window,
+        }
+    }
+    /// Reset the state of both directions
+    pub fn reset(&mut self) {
+        self.forward = Ranking::new(self.window);
+        self.backward = Ranking::new(self.window);
+        self.initial = Ranking::new(self.window);
+    }
+}
+
+impl Default for WeightedFrequencyCount {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Implementation of the Transform trait for Weighted Frequency Count
+impl Transform for WeightedFrequencyCount {
+    fn transform(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        self.initial.copy_from(&self.forward);
+        let mut result: Vec<u8> = Vec::with_capacity(source.len());
+        for byte in source.iter() {
+            let pos = self
+                .forward
+                .table
+                .iter()
+                .position(|p| p == byte)
+                .ok_or(TransformError::SymbolNotFound(*byte))?;
+            debug!("Found {:?} at {:?}", byte, pos);
+            result.push(pos as u8);
+            self.forward.update(*byte, self.window);
+        }
+        Ok(result)
+    }
+    fn reverse(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let mut result: Vec<u8> = Vec::with_capacity(source.len());
+        for pos in source.iter() {
+            let symbol = self.backward.table[*pos as usize];
+            debug!("Found {:?} at {:?}", symbol, pos);
+            result.push(symbol);
+            self.backward.update(symbol, self.window);
+        }
+        Ok(result)
+    }
+
+    /// The table is saved as is, followed by the history starting with the most recent symbol
+    fn export_metadata(&self) -> Vec<u8> {
+        let mut result = self.initial.table.clone();
+        result.extend(self.initial.history.iter());
+        result
+    }
+    fn import_metadata(&mut self, metadata: &[u8]) -> Result<(), TransformError> {
+        let size = ALPHABET_SIZE as usize + 1;
+        if metadata.len() < size || metadata.len() - size > self.window {
+            return Err(TransformError::InvalidMetadata);
+        }
+        let mut seen = [false; ALPHABET_SIZE as usize + 1];
+        for symbol in metadata[..size].iter() {
+            if std::mem::replace(&mut seen[*symbol as usize], true) {
+                return Err(TransformError::InvalidMetadata);
+            }
+        }
+        self.backward.table.copy_from_slice(&metadata[..size]);
+        self.backward.history = metadata[size..].iter().copied().collect();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{
+        metadata_roundtrip_with, random_roundtrip, roundtrip, roundtrip_with, short_roundtrip_with,
+        transform,
+    };
+
+    #[test]
+    fn test_easy_transforms() {
+        transform::<WeightedFrequencyCount>("aab".as_bytes(), &[97, 0, 98]);
+        transform::<WeightedFrequencyCount>("abab".as_bytes(), &[97, 98, 1, 1]);
+    }
+
+    #[test]
+    fn test_frequent_symbols_stay_in_front() {
+        // 'a' is more frequent in the window than the just seen 'b'
+        transform::<WeightedFrequencyCount>("aaaaba".as_bytes(), &[97, 0, 0, 0, 98, 0]);
+    }
+
+    #[test]
+    fn test_easy_roundtrip() {
+        roundtrip::<WeightedFrequencyCount>("bananaaa".as_bytes());
+        roundtrip::<WeightedFrequencyCount>("compressioncode".as_bytes());
+        roundtrip_with(
+            &mut WeightedFrequencyCount::with_window(1),
+            "banana".as_bytes(),
+        );
+        roundtrip_with(
+            &mut WeightedFrequencyCount::with_window(64),
+            "banana".as_bytes(),
+        );
+    }
+
+    #[test]
+    fn test_stream_chunks() {
+        let mut model = WeightedFrequencyCount::new();
+        let first = model.transform("banana".as_bytes()).unwrap();
+        let second = model.transform("bandana".as_bytes()).unwrap();
+        assert_eq!(model.reverse(&first).unwrap(), "banana".as_bytes());
+        assert_eq!(model.reverse(&second).unwrap(), "bandana".as_bytes());
+    }
+
+    #[test]
+    fn test_metadata() {
+        let chunks: [&[u8]; 3] = [
+            "banana".as_bytes(),
+            "bandana".as_bytes(),
+            "ananas".as_bytes(),
+        ];
+        metadata_roundtrip_with(WeightedFrequencyCount::new, &chunks);
+        metadata_roundtrip_with(|| WeightedFrequencyCount::with_window(3), &chunks);
+        let mut model = WeightedFrequencyCount::with_window(3);
+        assert_eq!(model.export_metadata().len(), 256);
+        model.transform("abcd".as_bytes()).unwrap();
+        model.transform("e".as_bytes()).unwrap();
+        assert_eq!(model.export_metadata()[256..], *"dcb".as_bytes());
+        let mut metadata = model.export_metadata();
+        metadata.push(0);
+        assert!(model.import_metadata(&metadata).is_err());
+    }
+
+    #[test]
+    fn test_short_roundtrip() {
+        short_roundtrip_with(WeightedFrequencyCount::new);
+        transform::<WeightedFrequencyCount>(&[], &[]);
+    }
+
+    #[test]
+    fn test_random_roundtrip() {
+        random_roundtrip::<WeightedFrequencyCount>(10, 1_000);
+        random_roundtrip::<WeightedFrequencyCount>(10, 1_000);
+    }
+}
@@ -0,0 +1,369 @@
+//! XOR Residual Transformation
+//!
+//! Implementation of the floating point compression via XOR residuals as
+//! described for [Gorilla](https://www.vldb.org/pvldb/vol8/p1816-teller.pdf) and
+//! [FPC](https://userweb.cs.txstate.edu/~burtscher/research/FPC/).
+use crate::element::{from_elements, to_elements};
+use crate::varint::{push_varint, read_varint};
+use crate::{DataType, Float, Transform, TransformError};
+use log::debug;
+use rscompress_coding::{BitReader, BitWriter, CodingError};
+use std::marker::PhantomData;
+
+const FCM_TABLE_BITS: u32 = 16;
+
+/// Predictors for the XOR residual transformation
+///
+/// All predictors work on the bit representation of the values.
+/// The prediction of the first value is always `0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XorPredictor {
+    /// Previous value (Gorilla)
+    Previous,
+    /// Linear extrapolation of the last two bit patterns i.e. `2 * x[i-1] - x[i-2]`
+    Linear,
+    /// Finite context method (FPC): the value which followed the last time the
+    /// same context of high order bits was seen
+    Fcm,
+}
+
+/// Prediction state during the transformation
+struct Predictions {
+    predictor: XorPredictor,
+    mask: u64,
+    shift: u32,
+    previous: [u64; 2],
+    table: Vec<u64>,
+    hash: usize,
+}
+
+impl Predictions {
+    fn new(predictor: XorPredictor, bits: u32) -> Self {
+        let table = match predictor {
+            XorPredictor::Fcm => vec![0u64; 1 << FCM_TABLE_BITS],
+            _ => Vec::new(),
+        };
+        Predictions {
+            predictor,
+            mask: u64::MAX >> (64 - bits),
+            shift: bits - FCM_TABLE_BITS,
+            previous: [0, 0],
+            table,
+            hash: 0,
+        }
+    }
+    fn predict(&self) -> u64 {
+        match self.predictor {
+            XorPredictor::Previous => self.previous[0],
+            XorPredictor::Linear => {
+                self.previous[0]
+                    .wrapping_mul(2)
+                    .wrapping_sub(self.previous[1])
+                    & self.mask
+            }
+            XorPredictor::Fcm => self.table[self.hash],
+        }
+    }
+    fn update(&mut self, value: u64) {
+        self.previous = [value, self.previous[0]];
+        if self.predictor == XorPredictor::Fcm {
+            self.table[self.hash] = value;
+            self.hash =
+                ((self.hash << 6) ^ (value >> self.shift) as usize) & ((1 << FCM_TABLE_BITS) - 1);
+        }
+    }
+}
+
+/// XOR residual struct to save the predictor used for floating point values
+///
+/// Each value is XORed with its prediction. Good predictions result in residuals
+/// with many leading and trailing zeros, which are not written.
+/// The residuals are packed like in Gorilla:
+///
+/// - `0`: the residual is zero
+/// - `10`: the meaningful bits fit into the window of leading and trailing zeros
+///   of the previous residual and only the bits inside the window are written
+/// - `11`: the number of leading zeros and the length of the meaningful bits
+///   (5 bits each for `f32`, 6 bits each for `f64`) are followed by the meaningful bits
+///
+/// The transformation works on the bit representation, such that the values are reproduced
+/// bit-exactly including NaN payloads, infinities and signed zeros.
+/// The output starts with the number of values as variable-length integer.
+/// Trailing bytes, which do not form a complete value, are kept at the end.
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress_transformation::{XorPredictor, XorResidual};
+/// let model = XorResidual::<f64>::with_predictor(XorPredictor::Previous);
+/// let data = [21.5f64; 100];
+/// let encoded = model.encode(&data);
+/// assert!(encoded.len() < 30);
+/// assert_eq!(model.decode(&encoded).unwrap(), data);
+/// ```
+#[derive(Debug)]
+pub struct XorResidual<T: Float> {
+    predictor: XorPredictor,
+    element: PhantomData<T>,
+}
+
+impl<T: Float> XorResidual<T> {
+    pub fn new() -> Self {
+        Self::with_predictor(XorPredictor::Previous)
+    }
+    pub fn with_predictor(predictor: XorPredictor) -> Self {
+        XorResidual {
+            predictor,
+            element: PhantomData,
+        }
+    }
+    /// Number of bits needed to write the leading zeros and length of residuals
+    fn field_bits() -> u32 {
+        T::BITS.trailing_zeros()
+    }
+    /// Encode typed values into bytes
+    pub fn encode(&self, data: &[T]) -> Vec<u8> {
+        let mut result: Vec<u8> = Vec::new();
+        push_varint(&mut result, data.len());
+        let mut predictions = Predictions::new(self.predictor, T::BITS);
+        let mut writer = BitWriter::new();
+        let mut window: Option<(u32, u32)> = None;
+        for value in data.iter() {
+            let bits = value.to_bits64();
+            let residual = bits ^ predictions.predict();
+            predictions.update(bits);
+            if residual == 0 {
+                writer.write_bit(false);
+                continue;
+            }
+            writer.write_bit(true);
+            let leading = residual.leading_zeros() - (64 - T::BITS);
+            let trailing = residual.trailing_zeros();
+            match window {
+                Some((l, t)) if leading >= l && trailing >= t => {
+                    writer.write_bit(false);
+                    writer.write(residual >> t, T::BITS - l - t);
+                }
+                _ => {
+                    let meaningful = T::BITS - leading - trailing;
+                    writer.write_bit(true);
+                    writer.write(leading as u64, Self::field_bits());
+                    writer.write((meaningful - 1) as u64, Self::field_bits());
+                    writer.write(residual >> trailing, meaningful);
+                    window = Some((leading, trailing));
+                }
+            }
+        }
+        result.extend(writer.finish());
+        result
+    }
+    /// Decode typed values and return them with the number of bytes read
+    fn decode_with_length(&self, source: &[u8]) -> Result<(Vec<T>, usize), TransformError> {
+        let mut pos = 0;
+        let count = read_varint(source, &mut pos)?;
+        let mut predictions = Predictions::new(self.predictor, T::BITS);
+        let mut reader = BitReader::new(&source[pos..]);
+        let truncated = |_: CodingError| TransformError::TruncatedBuffer;
+        let mut window: Option<(u32, u32)> = None;
+        let mut result: Vec<T> = Vec::with_capacity(count.min(source.len() * 8));
+        for _ in 0..count {
+            let mut residual = 0u64;
+            if reader.read_bit().map_err(truncated)? {
+                if reader.read_bit().map_err(truncated)? {
+                    let leading = reader.read(Self::field_bits()).map_err(truncated)? as u32;
+                    let meaningful = reader.read(Self::field_bits()).map_err(truncated)? as u32 + 1;
+                    let trailing = T::BITS
+                        .checked_sub(leading + meaningful)
+                        .ok_or(TransformError::InvalidSymbol(meaningful as u16))?;
+                    residual = reader.read(meaningful).map_err(truncated)? << trailing;
+                    window = Some((leading, trailing));
+                } else {
+                    let (l, t) = window.ok_or(TransformError::InvalidSymbol(0b10))?;
+                    residual = reader.read(T::BITS - l - t).map_err(truncated)? << t;
+                }
+            }
+            let bits = residual ^ predictions.predict();
+            predictions.update(bits);
+            result.push(T::from_bits64(bits));
+        }
+        Ok((result, pos + reader.bytes_read()))
+    }
+    /// Decode bytes into typed values
+    pub fn decode(&self, source: &[u8]) -> Result<Vec<T>, TransformError> {
+        Ok(self.decode_with_length(source)?.0)
+    }
+}
+
+impl<T: Float> Default for XorResidual<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Implementation of the Transform trait for XOR Residuals
+impl<T: Float> Transform for XorResidual<T> {
+    fn transform(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let (elements, trailing) = to_elements::<T>(source);
+        let mut result = self.encode(&elements);
+        result.extend_from_slice(trailing);
+        debug!(
+            "XOR residuals of {} bytes into {}",
+            source.len(),
+            result.len()
+        );
+        Ok(result)
+    }
+
+    fn reverse(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        if source.is_empty() {
+            return Ok(Vec::new());
+        }
+        let (elements, length) = self.decode_with_length(source)?;
+        Ok(from_elements(&elements, &source[length..]))
+    }
+
+    fn accepts(&self, dtype: DataType) -> bool {
+        dtype == T::DTYPE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{
+        random_roundtrip, random_roundtrip_with, roundtrip, roundtrip_with, short_roundtrip_with,
+    };
+
+    const PREDICTORS: [XorPredictor; 3] = [
+        XorPredictor::Previous,
+        XorPredictor::Linear,
+        XorPredictor::Fcm,
+    ];
+
+    fn special_f32() -> Vec<f32> {
+        vec![
+            0.0,
+            -0.0,
+            f32::NAN,
+            f32::from_bits(0x7fc0_1234),
+            f32::from_bits(0xffa0_0001),
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::MIN_POSITIVE,
+            f32::from_bits(1),
+            f32::MAX,
+            -1.5,
+        ]
+    }
+
+    fn special_f64() -> Vec<f64> {
+        vec![
+            0.0,
+            -0.0,
+            f64::NAN,
+            f64::from_bits(0x7ff8_0000_dead_beef),
+            f64::from_bits(0xfff0_0000_0000_0001),
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::MIN_POSITIVE,
+            f64::from_bits(1),
+            f64::MAX,
+            -1.5,
+        ]
+    }
+
+    #[test]
+    fn test_bit_exact_special_values() {
+        for predictor in PREDICTORS.iter() {
+            let model = XorResidual::<f32>::with_predictor(*predictor);
+            let data = special_f32();
+            let decoded = model.decode(&model.encode(&data)).unwrap();
+            let bits: Vec<u32> = decoded.iter().map(|x| x.to_bits()).collect();
+            let expected: Vec<u32> = data.iter().map(|x| x.to_bits()).collect();
+            assert_eq!(bits, expected);
+
+            let model = XorResidual::<f64>::with_predictor(*predictor);
+            let data = special_f64();
+            let decoded = model.decode(&model.encode(&data)).unwrap();
+            let bits: Vec<u64> = decoded.iter().map(|x| x.to_bits()).collect();
+            let expected: Vec<u64> = data.iter().map(|x| x.to_bits()).collect();
+            assert_eq!(bits, expected);
+        }
+    }
+
+    #[test]
+    fn test_packing() {
+        let model = XorResidual::<f32>::new();
+        // two values, the first residual 0x3fc00000 is written as '11' + 2 leading zeros
+        // (5 bits) + 8 meaningful bits (5 bits) + '11111111', the second residual as '0'
+        assert_eq!(
+            model.encode(&[1.5, 1.5]),
+            [2, 0b1100_0100, 0b0111_1111, 0b1111_0000]
+        );
+    }
+
+    #[test]
+    fn test_predictors_reduce_size() {
+        let data: Vec<f64> = (0..1000).map(|x| x as f64).collect();
+        let raw = data.len() * 8;
+        for predictor in PREDICTORS.iter() {
+            let model = XorResidual::<f64>::with_predictor(*predictor);
+            assert!(model.encode(&data).len() < raw);
+        }
+        let linear = XorResidual::<f64>::with_predictor(XorPredictor::Linear);
+        let previous = XorResidual::<f64>::new();
+        assert!(linear.encode(&data).len() < previous.encode(&data).len());
+    }
+
+    #[test]
+    fn test_truncated_decode() {
+        let model = XorResidual::<f64>::new();
+        let encoded = model.encode(&[1.0, 2.0, 3.0]);
+        assert!(model.decode(&encoded[..encoded.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        roundtrip::<XorResidual<f32>>(&[8, 2, 2, 2, 24, 32, 32, 1, 24]);
+        roundtrip::<XorResidual<f64>>("compressioncode".as_bytes());
+        for predictor in PREDICTORS.iter() {
+            roundtrip_with(
+                &mut XorResidual::<f64>::with_predictor(*predictor),
+                &[3u8; 1_001],
+            );
+        }
+    }
+
+    #[test]
+    fn test_short_roundtrip() {
+        for predictor in PREDICTORS.iter() {
+            short_roundtrip_with(|| XorResidual::<f32>::with_predictor(*predictor));
+            short_roundtrip_with(|| XorResidual::<f64>::with_predictor(*predictor));
+        }
+    }
+
+    #[test]
+    fn test_random_roundtrip() {
+        random_roundtrip::<XorResidual<f32>>(10, 10_001);
+        random_roundtrip::<XorResidual<f64>>(10, 10_001);
+        for predictor in PREDICTORS.iter() {
+            random_roundtrip_with(
+                || XorResidual::<f32>::with_predictor(*predictor),
+                10,
+                10_003,
+            );
+            random_roundtrip_with(
+                || XorResidual::<f64>::with_predictor(*predictor),
+                10,
+                10_003,
+            );
+        }
+    }
+
+    #[test]
+    fn test_accepts() {
+        assert!(XorResidual::<f64>::new().accepts(DataType::F64));
+        assert!(!XorResidual::<f64>::new().accepts(DataType::F32));
+        assert!(!XorResidual::<f64>::new().accepts(DataType::U64));
+    }
+}
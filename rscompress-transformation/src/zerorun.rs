@@ -0,0 +1,267 @@
+//! Zero-run Transformation
+//!
+//! Implementation of the zero-run-length encoding used by
+//! [bzip2](https://en.wikipedia.org/wiki/Bzip2) after the Move-To-Front stage.
+use crate::{Transform, TransformError};
+use log::debug;
+
+/// Symbol representing the binary digit `1` of a zero run
+pub const RUNA: u16 = 0;
+/// Symbol representing the binary digit `2` of a zero run
+pub const RUNB: u16 = 1;
+/// Number of symbols in the widened alphabet i.e. `RUNA`, `RUNB` and the 255 non-zero bytes
+pub const ZERO_RUN_ALPHABET_SIZE: usize = 257;
+/// Maximal length of a single encoded zero run, i.e. `MAX_RUN_DIGITS` times `RUNB`
+pub const MAX_ZERO_RUN: usize = (1 << (MAX_RUN_DIGITS + 1)) - 2;
+
+/// Number of digits after which a zero run is complete
+const MAX_RUN_DIGITS: usize = 16;
+
+const ESCAPE: u8 = 255;
+
+/// Zero-run struct for encoding runs of zeros as RUNA/RUNB symbols
+///
+/// After the Burrow-Wheeler and Move-To-Front transformations the data is
+/// dominated by runs of zeros. Each run of length `n` is written in
+/// [bijective base-2](https://en.wikipedia.org/wiki/Bijective_numeration)
+/// using the digits `RUNA` (1) and `RUNB` (2), least significant digit first.
+/// A run of length `n` therefore needs only about `log2(n + 1)` symbols.
+/// All non-zero bytes `v` are shifted to `v + 1`, which results in an alphabet of
+/// [`ZERO_RUN_ALPHABET_SIZE`] symbols.
+///
+/// # Byte representation
+/// The widened alphabet can be accessed via [`ZeroRunLength::to_symbols`]
+/// and [`ZeroRunLength::from_symbols`], e.g. for entropy coding.
+/// The implementation of the `Transform` trait maps it back to bytes.
+/// Symbols `0..=254` are written as is, the symbols `255` and `256` (the bytes `254` and `255`)
+/// are written as the escape byte `255` followed by `0` or `1`.
+///
+/// # Limits
+/// Few symbols describe very long runs, e.g. 60 `RUNB` symbols a run of more than 2^60 zeros.
+/// A run is therefore complete after 16 digits, i.e. at most [`MAX_ZERO_RUN`] zeros.
+/// Longer runs are split into several runs, such that reversing arbitrary input
+/// expands the data at most `MAX_ZERO_RUN / 16` times.
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress_transformation::{Transform, ZeroRunLength, RUNA, RUNB};
+/// let symbols = ZeroRunLength::to_symbols(&[0, 0, 0, 0, 7]);
+/// assert_eq!(symbols, [RUNB, RUNA, 8]);
+///
+/// let mut model = ZeroRunLength::new();
+/// let transformed = model.transform(&[0u8; 100]).unwrap();
+/// assert_eq!(transformed.len(), 6);
+/// ```
+#[derive(Debug)]
+pub struct ZeroRunLength {}
+
+impl ZeroRunLength {
+    pub fn new() -> Self {
+        ZeroRunLength {}
+    }
+
+    /// Encode bytes into the widened alphabet with RUNA/RUNB symbols
+    pub fn to_symbols(source: &[u8]) -> Vec<u16> {
+        let mut result: Vec<u16> = Vec::with_capacity(source.len());
+        let mut run = 0usize;
+        for byte in source.iter() {
+            if *byte == 0 {
+                run += 1;
+                continue;
+            }
+            push_run(&mut result, run);
+            run = 0;
+            result.push(*byte as u16 + 1);
+        }
+        push_run(&mut result, run);
+        result
+    }
+
+    /// Decode symbols of the widened alphabet back to bytes
+    ///
+    /// A run is complete after 16 digits, even if more `RUNA` or `RUNB` symbols follow.
+    pub fn from_symbols(source: &[u16]) -> Result<Vec<u8>, TransformError> {
+        let mut result: Vec<u8> = Vec::with_capacity(source.len());
+        let mut run = 0usize;
+        let mut digits = 0;
+        for symbol in source.iter() {
+            match *symbol {
+                RUNA | RUNB => {
+                    run += (*symbol as usize + 1) << digits;
+                    digits += 1;
+                    if digits == MAX_RUN_DIGITS {
+                        result.resize(result.len() + run, 0);
+                        run = 0;
+                        digits = 0;
+                    }
+                }
+                s if (s as usize) < ZERO_RUN_ALPHABET_SIZE => {
+                    result.resize(result.len() + run, 0);
+                    run = 0;
+                    digits = 0;
+                    result.push((s - 1) as u8);
+                }
+                s => return Err(TransformError::InvalidSymbol(s)),
+            }
+        }
+        result.resize(result.len() + run, 0);
+        Ok(result)
+    }
+}
+
+impl Default for ZeroRunLength {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Append the RUNA/RUNB representation of a zero run of length `run`
+///
+/// Runs longer than [`MAX_ZERO_RUN`] are split into several runs.
+fn push_run(result: &mut Vec<u16>, mut run: usize) {
+    while run > MAX_ZERO_RUN {
+        result.extend_from_slice(&[RUNB; MAX_RUN_DIGITS]);
+        run -= MAX_ZERO_RUN;
+    }
+    while run > 0 {
+        if run % 2 == 1 {
+            result.push(RUNA);
+            run = (run - 1) / 2;
+        } else {
+            result.push(RUNB);
+            run = (run - 2) / 2;
+        }
+    }
+}
+
+/// Implementation of the Transform trait for zero-run-length encoding
+impl Transform for ZeroRunLength {
+    fn transform(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let symbols = Self::to_symbols(source);
+        let mut result: Vec<u8> = Vec::with_capacity(symbols.len());
+        for symbol in symbols.iter() {
+            if *symbol < ESCAPE as u16 {
+                result.push(*symbol as u8);
+            } else {
+                result.push(ESCAPE);
+                result.push((*symbol - ESCAPE as u16) as u8);
+            }
+        }
+        debug!(
+            "Zero-run encoded {} bytes into {}",
+            source.len(),
+            result.len()
+        );
+        Ok(result)
+    }
+
+    fn reverse(&mut self, source: &[u8]) -> Result<Vec<u8>, TransformError> {
+        let mut symbols: Vec<u16> = Vec::with_capacity(source.len());
+        let mut bytes = source.iter();
+        while let Some(byte) = bytes.next() {
+            if *byte == ESCAPE {
+                let next = *bytes.next().ok_or(TransformError::TruncatedBuffer)?;
+                if next > 1 {
+                    return Err(TransformError::SymbolNotFound(next));
+                }
+                symbols.push(ESCAPE as u16 + next as u16);
+            } else {
+                symbols.push(*byte as u16);
+            }
+        }
+        Self::from_symbols(&symbols)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{random_roundtrip, reverse, roundtrip, short_roundtrip_with, transform};
+
+    #[test]
+    fn test_bijective_runs() {
+        let expected: [&[u16]; 7] = [
+            &[RUNA],
+            &[RUNB],
+            &[RUNA, RUNA],
+            &[RUNB, RUNA],
+            &[RUNA, RUNB],
+            &[RUNB, RUNB],
+            &[RUNA, RUNA, RUNA],
+        ];
+        for (n, e) in expected.iter().enumerate() {
+            let zeros = vec![0u8; n + 1];
+            assert_eq!(ZeroRunLength::to_symbols(&zeros), *e);
+            assert_eq!(ZeroRunLength::from_symbols(e).unwrap(), zeros);
+        }
+    }
+
+    #[test]
+    fn test_easy_transforms() {
+        transform::<ZeroRunLength>(&[0, 0, 0, 5, 0, 1], &[0, 0, 6, 0, 2]);
+        transform::<ZeroRunLength>(&[254, 255, 3], &[255, 0, 255, 1, 4]);
+    }
+
+    #[test]
+    fn test_easy_reverses() {
+        reverse::<ZeroRunLength>(&[0, 0, 6, 0, 2], &[0, 0, 0, 5, 0, 1]);
+        reverse::<ZeroRunLength>(&[255, 0, 255, 1, 4], &[254, 255, 3]);
+    }
+
+    #[test]
+    fn test_invalid_reverses() {
+        let mut model = ZeroRunLength::new();
+        assert!(model.reverse(&[3, 255]).is_err());
+        assert!(model.reverse(&[255, 2]).is_err());
+        assert!(ZeroRunLength::from_symbols(&[ZERO_RUN_ALPHABET_SIZE as u16]).is_err());
+    }
+
+    #[test]
+    fn test_run_limit() {
+        let longest = ZeroRunLength::to_symbols(&vec![0u8; MAX_ZERO_RUN]);
+        assert_eq!(longest, [RUNB; MAX_RUN_DIGITS]);
+        for n in [MAX_ZERO_RUN + 1, 2 * MAX_ZERO_RUN, 2 << 20].iter() {
+            let zeros = vec![0u8; *n];
+            let symbols = ZeroRunLength::to_symbols(&zeros);
+            assert_eq!(ZeroRunLength::from_symbols(&symbols).unwrap(), zeros);
+        }
+        roundtrip::<ZeroRunLength>(&vec![0u8; 2 << 20]);
+        // 64 RUNB symbols would describe a run of almost 2^65 zeros
+        let mut model = ZeroRunLength::new();
+        let result = model.reverse(&[RUNB as u8; 64]).unwrap();
+        assert_eq!(result.len(), 4 * MAX_ZERO_RUN);
+        let result = model.reverse(&[RUNB as u8; 200]).unwrap();
+        assert!(result.len() <= 200 * MAX_ZERO_RUN / MAX_RUN_DIGITS);
+    }
+
+    #[test]
+    fn test_reduces_length() {
+        let mut data = vec![0u8; 10_000];
+        data.extend_from_slice(&[1, 2, 0, 0, 3]);
+        let mut model = ZeroRunLength::new();
+        let result = model.transform(&data).unwrap();
+        assert!(result.len() < 20);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        roundtrip::<ZeroRunLength>(&[0, 0, 0, 5, 0, 1]);
+        roundtrip::<ZeroRunLength>(&[0]);
+        roundtrip::<ZeroRunLength>(&[255, 255, 0, 254]);
+        roundtrip::<ZeroRunLength>(&[0u8; 1_000]);
+        roundtrip::<ZeroRunLength>("compressioncode".as_bytes());
+    }
+
+    #[test]
+    fn test_short_roundtrip() {
+        short_roundtrip_with(ZeroRunLength::new);
+        transform::<ZeroRunLength>(&[], &[]);
+    }
+
+    #[test]
+    fn test_random_roundtrip() {
+        random_roundtrip::<ZeroRunLength>(100, 10_000);
+        random_roundtrip::<ZeroRunLength>(100, 10_000);
+    }
+}
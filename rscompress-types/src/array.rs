@@ -0,0 +1,271 @@
+//! Typed Arrays
+//!
+//! Arrays of primitive numbers kept as bytes together with their layout.
+//! Byte-level stages work on the bytes of contiguous arrays, while typed stages
+//! read the elements in the type and byte order given by the layout.
+use crate::{DataType, Endianness, Primitive, TypeError};
+
+/// Layout of the elements of an array in memory
+///
+/// The dimensions are ordered with the fastest varying first, i.e. the element
+/// `(i0, i1, ..)` is found at the element offset `i0 * strides[0] + i1 * strides[1] + ..`.
+/// Contiguous arrays have the strides `[1, shape[0], shape[0] * shape[1], ..]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    pub dtype: DataType,
+    pub endianness: Endianness,
+    pub shape: Vec<usize>,
+    pub strides: Vec<usize>,
+}
+
+impl Layout {
+    /// Contiguous layout in little-endian order
+    pub fn new(dtype: DataType, shape: &[usize]) -> Self {
+        Layout {
+            dtype,
+            endianness: Endianness::Little,
+            shape: shape.to_vec(),
+            strides: contiguous_strides(shape),
+        }
+    }
+    /// Layout with the given byte order and strides (in elements)
+    pub fn with_endianness_and_strides(
+        dtype: DataType,
+        endianness: Endianness,
+        shape: &[usize],
+        strides: &[usize],
+    ) -> Self {
+        Layout {
+            dtype,
+            endianness,
+            shape: shape.to_vec(),
+            strides: strides.to_vec(),
+        }
+    }
+    /// Number of elements
+    pub fn len(&self) -> usize {
+        self.shape.iter().product()
+    }
+    /// Check if the array has no elements
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Check if the elements are stored without gaps in memory order
+    pub fn is_contiguous(&self) -> bool {
+        self.strides == contiguous_strides(&self.shape)
+    }
+    /// Number of bytes needed to store all elements, or `None` on overflow
+    pub fn byte_len(&self) -> Option<usize> {
+        if self.is_empty() {
+            return Some(0);
+        }
+        let last = self
+            .shape
+            .iter()
+            .zip(self.strides.iter())
+            .try_fold(0usize, |offset, (n, s)| {
+                offset.checked_add((n - 1).checked_mul(*s)?)
+            })?;
+        last.checked_add(1)?.checked_mul(self.dtype.size())
+    }
+    /// Element offsets of all elements in memory order
+    fn offsets(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len()).map(move |index| {
+            let mut rest = index;
+            let mut offset = 0;
+            for (n, s) in self.shape.iter().zip(self.strides.iter()) {
+                offset += (rest % n) * s;
+                rest /= n;
+            }
+            offset
+        })
+    }
+}
+
+/// Strides of a contiguous array with the fastest varying dimension first
+fn contiguous_strides(shape: &[usize]) -> Vec<usize> {
+    shape
+        .iter()
+        .scan(1usize, |stride, n| {
+            let current = *stride;
+            *stride = stride.saturating_mul(*n);
+            Some(current)
+        })
+        .collect()
+}
+
+/// Array struct with the layout and the bytes of the elements
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress_types::{Array, DataType};
+/// let array = Array::from_slice(&[1.5f32, 2.5, 3.5, 4.5, 5.5, 6.5], &[3, 2]).unwrap();
+/// assert_eq!(array.layout().dtype, DataType::F32);
+/// assert_eq!(array.as_bytes().len(), 24);
+/// assert_eq!(array.to_vec::<f32>().unwrap()[4], 5.5);
+/// assert!(array.to_vec::<f64>().is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Array {
+    layout: Layout,
+    bytes: Vec<u8>,
+}
+
+impl Array {
+    /// Contiguous little-endian array of the elements
+    pub fn from_slice<T: Primitive>(data: &[T], shape: &[usize]) -> Result<Self, TypeError> {
+        let layout = Layout::new(T::DTYPE, shape);
+        if layout.len() != data.len() {
+            return Err(TypeError::ShapeMismatch(layout.len(), data.len()));
+        }
+        let mut bytes = Vec::with_capacity(data.len() * T::DTYPE.size());
+        for x in data.iter() {
+            x.write_bytes(Endianness::Little, &mut bytes);
+        }
+        Ok(Array { layout, bytes })
+    }
+    /// Array of the bytes with the given layout
+    pub fn with_layout(layout: Layout, bytes: Vec<u8>) -> Result<Self, TypeError> {
+        if layout.shape.len() != layout.strides.len() {
+            return Err(TypeError::InvalidStrides);
+        }
+        let needed = layout.byte_len().ok_or(TypeError::InvalidStrides)?;
+        if bytes.len() < needed {
+            return Err(TypeError::ShapeMismatch(needed, bytes.len()));
+        }
+        Ok(Array { layout, bytes })
+    }
+    /// Layout of the elements
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+    /// Bytes of the elements as stored
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+    /// Bytes of the elements as stored
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+    /// Elements in memory order converted from the byte order of the layout
+    pub fn to_vec<T: Primitive>(&self) -> Result<Vec<T>, TypeError> {
+        if T::DTYPE != self.layout.dtype {
+            return Err(TypeError::TypeMismatch(T::DTYPE, self.layout.dtype));
+        }
+        let size = T::DTYPE.size();
+        let result = self
+            .layout
+            .offsets()
+            .map(|offset| T::from_bytes(&self.bytes[offset * size..], self.layout.endianness))
+            .collect();
+        Ok(result)
+    }
+    /// Copy of the array with contiguous elements in the same byte order
+    ///
+    /// Byte-level stages, which expect the elements in memory order, work on
+    /// the bytes of contiguous arrays.
+    pub fn contiguous(&self) -> Self {
+        if self.layout.is_contiguous() {
+            return self.clone();
+        }
+        let size = self.layout.dtype.size();
+        let mut bytes = Vec::with_capacity(self.layout.len() * size);
+        for offset in self.layout.offsets() {
+            bytes.extend_from_slice(&self.bytes[offset * size..(offset + 1) * size]);
+        }
+        let mut layout = Layout::new(self.layout.dtype, &self.layout.shape);
+        layout.endianness = self.layout.endianness;
+        Array { layout, bytes }
+    }
+    /// Copy of the array with contiguous elements in the given byte order
+    ///
+    /// Typed stages reading the bytes in little-endian order work on arrays
+    /// normalized with `to_endianness(Endianness::Little)`.
+    pub fn to_endianness(&self, endianness: Endianness) -> Self {
+        let mut result = self.contiguous();
+        if endianness != self.layout.endianness {
+            let size = self.layout.dtype.size();
+            let len = self.layout.len() * size;
+            for element in result.bytes[..len].chunks_exact_mut(size) {
+                element.reverse();
+            }
+            result.bytes.truncate(len);
+            result.layout.endianness = endianness;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        let layout = Layout::new(DataType::U16, &[4, 3, 2]);
+        assert_eq!(layout.strides, [1, 4, 12]);
+        assert_eq!(layout.len(), 24);
+        assert_eq!(layout.byte_len(), Some(48));
+        assert!(layout.is_contiguous());
+        let transposed =
+            Layout::with_endianness_and_strides(DataType::U16, Endianness::Big, &[3, 4], &[4, 1]);
+        assert!(!transposed.is_contiguous());
+        assert_eq!(
+            transposed.offsets().take(4).collect::<Vec<_>>(),
+            [0, 4, 8, 1]
+        );
+        assert_eq!(Layout::new(DataType::F64, &[5, 0]).byte_len(), Some(0));
+        let overflow = Layout::with_endianness_and_strides(
+            DataType::F64,
+            Endianness::Little,
+            &[3],
+            &[usize::MAX],
+        );
+        assert_eq!(overflow.byte_len(), None);
+    }
+
+    #[test]
+    fn test_strided_array() {
+        // column-major 2x3 matrix in big-endian order, read row by row
+        let values = [1i32, 4, 2, 5, 3, 6];
+        let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_be_bytes()).collect();
+        let layout =
+            Layout::with_endianness_and_strides(DataType::I32, Endianness::Big, &[3, 2], &[2, 1]);
+        let array = Array::with_layout(layout, bytes).unwrap();
+        assert_eq!(array.to_vec::<i32>().unwrap(), [1, 2, 3, 4, 5, 6]);
+        let contiguous = array.contiguous();
+        assert!(contiguous.layout().is_contiguous());
+        assert_eq!(contiguous.layout().endianness, Endianness::Big);
+        assert_eq!(contiguous.as_bytes()[..8], [0, 0, 0, 1, 0, 0, 0, 2]);
+        assert_eq!(contiguous.to_vec::<i32>().unwrap(), [1, 2, 3, 4, 5, 6]);
+        let little = array.to_endianness(Endianness::Little);
+        assert_eq!(little.as_bytes()[..8], [1, 0, 0, 0, 2, 0, 0, 0]);
+        assert_eq!(little.to_vec::<i32>().unwrap(), [1, 2, 3, 4, 5, 6]);
+        assert_eq!(little.to_endianness(Endianness::Big), contiguous);
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(matches!(
+            Array::from_slice(&[1u8, 2, 3], &[2, 2]),
+            Err(TypeError::ShapeMismatch(4, 3))
+        ));
+        let layout = Layout::new(DataType::F32, &[2, 2]);
+        assert!(matches!(
+            Array::with_layout(layout.clone(), vec![0; 15]),
+            Err(TypeError::ShapeMismatch(16, 15))
+        ));
+        let mut invalid = layout.clone();
+        invalid.strides.pop();
+        assert!(matches!(
+            Array::with_layout(invalid, vec![0; 16]),
+            Err(TypeError::InvalidStrides)
+        ));
+        let array = Array::with_layout(layout, vec![0; 16]).unwrap();
+        assert!(matches!(
+            array.to_vec::<u32>(),
+            Err(TypeError::TypeMismatch(DataType::U32, DataType::F32))
+        ));
+    }
+}
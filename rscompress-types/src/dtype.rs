@@ -0,0 +1,173 @@
+//! Element Types
+//!
+//! Description of the primitive numbers stored in arrays and their byte order.
+use std::fmt::Debug;
+
+/// Types of the elements of arrays
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DataType {
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+/// All element types in the order of their identifiers
+const DATA_TYPES: [DataType; 10] = [
+    DataType::U8,
+    DataType::U16,
+    DataType::U32,
+    DataType::U64,
+    DataType::I8,
+    DataType::I16,
+    DataType::I32,
+    DataType::I64,
+    DataType::F32,
+    DataType::F64,
+];
+
+impl DataType {
+    /// Number of bytes of a single element
+    pub fn size(&self) -> usize {
+        match *self {
+            DataType::U8 | DataType::I8 => 1,
+            DataType::U16 | DataType::I16 => 2,
+            DataType::U32 | DataType::I32 | DataType::F32 => 4,
+            DataType::U64 | DataType::I64 | DataType::F64 => 8,
+        }
+    }
+    /// Check if the elements are floating point numbers
+    pub fn is_float(&self) -> bool {
+        matches!(*self, DataType::F32 | DataType::F64)
+    }
+    /// Check if the elements are signed numbers
+    pub fn is_signed(&self) -> bool {
+        !matches!(
+            *self,
+            DataType::U8 | DataType::U16 | DataType::U32 | DataType::U64
+        )
+    }
+    /// Identifier of the element type in headers of containers
+    pub fn id(&self) -> u8 {
+        DATA_TYPES.iter().position(|t| t == self).unwrap() as u8
+    }
+    /// Element type of the identifier
+    pub fn from_id(id: u8) -> Option<Self> {
+        DATA_TYPES.get(id as usize).copied()
+    }
+}
+
+/// Byte order of the elements
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endianness {
+    /// Least significant byte first
+    Little,
+    /// Most significant byte first
+    Big,
+}
+
+impl Endianness {
+    /// Byte order of the host
+    pub fn native() -> Self {
+        if cfg!(target_endian = "big") {
+            Endianness::Big
+        } else {
+            Endianness::Little
+        }
+    }
+    /// Identifier of the byte order in headers of containers
+    pub fn id(&self) -> u8 {
+        match *self {
+            Endianness::Little => 0,
+            Endianness::Big => 1,
+        }
+    }
+    /// Byte order of the identifier
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Endianness::Little),
+            1 => Some(Endianness::Big),
+            _ => None,
+        }
+    }
+}
+
+/// Trait for primitive numbers which can be stored in arrays
+pub trait Primitive: Copy + Debug + Default + PartialEq {
+    /// Element type of the number
+    const DTYPE: DataType;
+    /// Read the number from the first bytes in the given byte order
+    fn from_bytes(bytes: &[u8], endianness: Endianness) -> Self;
+    /// Append the number as bytes in the given byte order
+    fn write_bytes(self, endianness: Endianness, result: &mut Vec<u8>);
+}
+
+macro_rules! impl_primitive {
+    ($($t:ty => $d:ident),*) => {
+        $(
+            impl Primitive for $t {
+                const DTYPE: DataType = DataType::$d;
+                fn from_bytes(bytes: &[u8], endianness: Endianness) -> Self {
+                    let mut buffer = [0u8; std::mem::size_of::<$t>()];
+                    buffer.copy_from_slice(&bytes[..std::mem::size_of::<$t>()]);
+                    match endianness {
+                        Endianness::Little => <$t>::from_le_bytes(buffer),
+                        Endianness::Big => <$t>::from_be_bytes(buffer),
+                    }
+                }
+                fn write_bytes(self, endianness: Endianness, result: &mut Vec<u8>) {
+                    match endianness {
+                        Endianness::Little => result.extend_from_slice(&self.to_le_bytes()),
+                        Endianness::Big => result.extend_from_slice(&self.to_be_bytes()),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_primitive!(u8 => U8, u16 => U16, u32 => U32, u64 => U64, i8 => I8, i16 => I16, i32 => I32, i64 => I64, f32 => F32, f64 => F64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_data_types() {
+        for (i, dtype) in DATA_TYPES.iter().enumerate() {
+            assert_eq!(dtype.id() as usize, i);
+            assert_eq!(DataType::from_id(i as u8), Some(*dtype));
+        }
+        assert_eq!(DataType::from_id(10), None);
+        assert_eq!(f32::DTYPE.size(), 4);
+        assert!(f64::DTYPE.is_float() && f64::DTYPE.is_signed());
+        assert!(!u16::DTYPE.is_signed() && !i16::DTYPE.is_float());
+    }
+
+    #[test]
+    fn test_byte_order() {
+        let mut little = Vec::new();
+        let mut big = Vec::new();
+        0x0102_0304u32.write_bytes(Endianness::Little, &mut little);
+        0x0102_0304u32.write_bytes(Endianness::Big, &mut big);
+        assert_eq!(little, [4, 3, 2, 1]);
+        assert_eq!(big, [1, 2, 3, 4]);
+        assert_eq!(u32::from_bytes(&big, Endianness::Big), 0x0102_0304);
+        assert_eq!(f64::from_bytes(&1.5f64.to_be_bytes(), Endianness::Big), 1.5);
+        assert_eq!(
+            Endianness::native() == Endianness::Little,
+            cfg!(target_endian = "little")
+        );
+        assert_eq!(
+            Endianness::from_id(Endianness::Big.id()),
+            Some(Endianness::Big)
+        );
+        assert_eq!(Endianness::from_id(2), None);
+    }
+}
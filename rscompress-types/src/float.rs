@@ -0,0 +1,55 @@
+//! Floating point numbers
+//!
+//! Helpers for stages working on the values or the bit representation of `f32` and `f64`.
+//! Calculations on the values can be done in `f64`, which represents every `f32` exactly.
+use crate::Primitive;
+
+/// Trait for floating point numbers exposing their values and bit representation
+pub trait Float: Primitive + PartialOrd {
+    /// Number of bits of the representation
+    const BITS: u32;
+    /// Number of explicitly stored mantissa bits
+    const MANTISSA_BITS: u32;
+    /// Lossless conversion into a `f64`
+    fn to_f64(self) -> f64;
+    /// Conversion from a `f64` rounding to the nearest representable number
+    fn from_f64(value: f64) -> Self;
+    /// Bit representation as the lowest bits of an `u64`
+    fn to_bits64(self) -> u64;
+    /// Float from the lowest bits of an `u64`
+    fn from_bits64(bits: u64) -> Self;
+}
+
+impl Float for f32 {
+    const BITS: u32 = 32;
+    const MANTISSA_BITS: u32 = 23;
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+    fn to_bits64(self) -> u64 {
+        self.to_bits() as u64
+    }
+    fn from_bits64(bits: u64) -> Self {
+        f32::from_bits(bits as u32)
+    }
+}
+
+impl Float for f64 {
+    const BITS: u32 = 64;
+    const MANTISSA_BITS: u32 = 52;
+    fn to_f64(self) -> f64 {
+        self
+    }
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+    fn to_bits64(self) -> u64 {
+        self.to_bits()
+    }
+    fn from_bits64(bits: u64) -> Self {
+        f64::from_bits(bits)
+    }
+}
@@ -0,0 +1,63 @@
+//! # rscompress-types
+//!
+//! `rscompress_types` implements the typed data model shared by the rscompress libraries.
+//!
+//! # Introduction
+//! Scientific data consists of arrays of primitive numbers. An [`Array`] keeps the
+//! bytes of the elements together with their [`Layout`], i.e. the element type,
+//! the byte order, the shape and the strides. Byte-level stages, like most
+//! transformations and checksums, work on the bytes of contiguous arrays. Typed
+//! transformations declare the [`DataType`]s they accept.
+use std::fmt;
+use std::{error::Error, fmt::Display};
+
+mod array;
+mod dtype;
+mod float;
+
+pub use array::{Array, Layout};
+pub use dtype::{DataType, Endianness, Primitive};
+pub use float::Float;
+
+/// An enum representing possible errors of typed arrays
+#[derive(Debug)]
+pub enum TypeError {
+    /// The element type (expected, actual) does not match
+    TypeMismatch(DataType, DataType),
+    /// The number of elements or bytes (expected, actual) does not match the layout
+    ShapeMismatch(usize, usize),
+    /// The strides do not match the shape or address more than `usize::MAX` bytes
+    InvalidStrides,
+}
+
+impl Error for TypeError {
+    fn description(&self) -> &str {
+        match *self {
+            TypeError::TypeMismatch(_, _) => "Type mismatch",
+            TypeError::ShapeMismatch(_, _) => "Shape mismatch",
+            TypeError::InvalidStrides => "Invalid strides",
+        }
+    }
+}
+
+impl Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            TypeError::TypeMismatch(expected, actual) => {
+                write!(
+                    f,
+                    "Expected elements of {:?} but got {:?}",
+                    expected, actual
+                )
+            }
+            TypeError::ShapeMismatch(expected, actual) => {
+                write!(
+                    f,
+                    "Expected {:?} elements or bytes but got {:?}",
+                    expected, actual
+                )
+            }
+            TypeError::InvalidStrides => write!(f, "Strides do not match the shape"),
+        }
+    }
+}
@@ -1,7 +1,224 @@
+//! # rscompress
+//!
+//! `rscompress` is the base library orchestrating the supporting libraries for
+//! transformation, approximation, coding and checksums into complete compressors.
+//!
+//! # Compressors
+//! - [`Sz`]: Error-bounded lossy compression of gridded floating point data
+//!   following the prediction-based [SZ](https://szcompressor.org/) approach
+//! - [`Zfp`]: Fixed-rate, fixed-precision and fixed-accuracy lossy compression of
+//!   gridded floating point data following the transform-based [ZFP](https://zfp.io/) approach
+//!
+//! Both implement the [`Compressor`] trait, which can evaluate the compression ratio
+//! and the errors of a round trip (see [`Compressor::evaluate`]).
+//! The compressed data is saved in little-endian order. The headers record the byte
+//! order of the original data, so typed arrays of any byte order are restored with
+//! the same values and byte order on hosts of any byte order (see [`Compressor::compress_array`]).
+use rscompress_approximation::{ApproximationError, ErrorReport};
+use rscompress_checksums::ChecksumError;
+use rscompress_coding::CodingError;
+use rscompress_types::TypeError;
+use std::fmt;
+use std::{error::Error, fmt::Display};
+
+mod sz;
+mod zfp;
+
+pub use rscompress_types::{Array, DataType, Endianness, Layout};
+pub use sz::{Lossy, Sz, SzHeader, SZ_MAGIC, SZ_RADIUS, SZ_VERSION};
+pub use zfp::{Zfp, ZfpHeader, ZfpMode, ZFP_MAGIC, ZFP_VERSION};
+
+/// Trait for lossy compressors of floating point data
+pub trait Compressor<T: Lossy> {
+    fn compress(&self, data: &[T]) -> Result<Vec<u8>, CompressionError>;
+    fn decompress(&self, source: &[u8]) -> Result<Vec<T>, CompressionError>;
+    /// Compress the elements of the array and record its byte order
+    fn compress_array(&self, array: &Array) -> Result<Vec<u8>, CompressionError>;
+    /// Decompress into a contiguous array in the recorded byte order
+    fn decompress_array(&self, source: &[u8]) -> Result<Array, CompressionError>;
+    /// Size of the `xy` slices of the grid for spatial error metrics
+    fn slice(&self) -> (usize, usize);
+    /// Compress and decompress the data and report the compression ratio
+    ///
+    /// The error statistics of the round trip are only calculated if `errors` is set.
+    fn evaluate(&self, data: &[T], errors: bool) -> Result<Evaluation, CompressionError> {
+        let compressed = self.compress(data)?;
+        let report = if errors {
+            let restored = self.decompress(&compressed)?;
+            let (nx, ny) = self.slice();
+            let report = match nx * ny {
+                0 => ErrorReport::new(data, &restored),
+                _ => ErrorReport::with_grid(data, &restored, nx, ny),
+            };
+            Some(report.map_err(CompressionError::Approximation)?)
+        } else {
+            None
+        };
+        let original = data.len() * T::DTYPE.size();
+        Ok(Evaluation {
+            original,
+            compressed: compressed.len(),
+            ratio: original as f64 / compressed.len() as f64,
+            errors: report,
+        })
+    }
+}
+
+/// Compression ratio and optional error statistics of a lossy round trip
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Evaluation {
+    /// Size of the original data in bytes
+    pub original: usize,
+    /// Size of the compressed data in bytes
+    pub compressed: usize,
+    pub ratio: f64,
+    pub errors: Option<ErrorReport>,
+}
+
+impl Display for Evaluation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} -> {} bytes (ratio {:.4})",
+            self.original, self.compressed, self.ratio
+        )?;
+        if let Some(errors) = &self.errors {
+            write!(f, ", {}", errors)?;
+        }
+        Ok(())
+    }
+}
+
+/// An enum representing possible errors during compression and decompression
+#[derive(Debug)]
+pub enum CompressionError {
+    /// The header of the compressed data is truncated or contains invalid fields
+    InvalidHeader,
+    /// The compressed data was written by an unsupported version of the format
+    UnsupportedVersion(u8),
+    /// The element type of the compressed data differs from the requested type
+    TypeMismatch(u8, u8),
+    /// The number of elements (expected, actual) does not match the shape
+    ShapeMismatch(usize, usize),
+    /// The parameters of the compressor are not valid
+    InvalidParameter,
+    /// The element at the index is not finite, but the compressor supports only finite values
+    NonFinite(usize),
+    /// The body of the compressed data is truncated or inconsistent with the header
+    InvalidBody,
+    /// The checksum (expected, actual) of the decompressed data does not match
+    ChecksumMismatch(u32, u32),
+    /// Error of the approximation
+    Approximation(ApproximationError),
+    /// Error of the coding
+    Coding(CodingError),
+    /// Error of the checksum
+    Checksum(ChecksumError),
+    /// Error of the typed array
+    Type(TypeError),
+}
+
+impl Error for CompressionError {
+    fn description(&self) -> &str {
+        match *self {
+            CompressionError::InvalidHeader => "Invalid header",
+            CompressionError::UnsupportedVersion(_) => "Unsupported version",
+            CompressionError::TypeMismatch(_, _) => "Type mismatch",
+            CompressionError::ShapeMismatch(_, _) => "Shape mismatch",
+            CompressionError::InvalidParameter => "Invalid parameter",
+            CompressionError::NonFinite(_) => "Non-finite value",
+            CompressionError::InvalidBody => "Invalid body",
+            CompressionError::ChecksumMismatch(_, _) => "Checksum mismatch",
+            CompressionError::Approximation(_) => "Approximation failed",
+            CompressionError::Coding(_) => "Coding failed",
+            CompressionError::Checksum(_) => "Checksum failed",
+            CompressionError::Type(_) => "Invalid array",
+        }
+    }
+}
+
+impl Display for CompressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompressionError::InvalidHeader => write!(f, "Header is truncated or invalid"),
+            CompressionError::UnsupportedVersion(v) => {
+                write!(f, "Version {} of the format is not supported", v)
+            }
+            CompressionError::TypeMismatch(expected, actual) => write!(
+                f,
+                "Element type {} was requested, but the data has type {}",
+                expected, actual
+            ),
+            CompressionError::ShapeMismatch(expected, actual) => write!(
+                f,
+                "Shape has {} elements, but the data has {}",
+                expected, actual
+            ),
+            CompressionError::InvalidParameter => {
+                write!(f, "Parameters of the compressor are not valid")
+            }
+            CompressionError::NonFinite(i) => write!(f, "Element {} is not finite", i),
+            CompressionError::InvalidBody => {
+                write!(f, "Body is truncated or does not match the header")
+            }
+            CompressionError::ChecksumMismatch(expected, actual) => write!(
+                f,
+                "Checksum {:#010x} was expected, but the data has {:#010x}",
+                expected, actual
+            ),
+            CompressionError::Approximation(e) => write!(f, "Approximation failed: {}", e),
+            CompressionError::Coding(e) => write!(f, "Coding failed: {}", e),
+            CompressionError::Checksum(e) => write!(f, "Checksum failed: {}", e),
+            CompressionError::Type(e) => write!(f, "Invalid array: {}", e),
+        }
+    }
+}
+
 #[cfg(test)]
-mod tests {
-    #[test]
-    fn it_works() {
-        assert_eq!(2 + 2, 4);
+#[allow(dead_code)]
+pub mod tests {
+    //! # Tests
+    //! This module defines helper functions for testing compressors.
+    use std::fs;
+    use std::sync::OnceLock;
+
+    /// Values of the testdata with normal distribution (mean 256, std 25.6)
+    pub fn normal_f32() -> &'static [f32] {
+        static DATA: OnceLock<Vec<f32>> = OnceLock::new();
+        DATA.get_or_init(|| {
+            let path = concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/../testdata/f32_256normal7.raw"
+            );
+            let bytes = fs::read(path).unwrap();
+            bytes
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect()
+        })
+    }
+
+    /// Smooth field on a `nx * ny * nz` grid
+    pub fn smooth_field(nx: usize, ny: usize, nz: usize) -> Vec<f64> {
+        (0..nx * ny * nz)
+            .map(|i| {
+                let (x, y, z) = (i % nx, (i / nx) % ny, i / (nx * ny));
+                let (x, y, z) = (x as f64 * 0.05, y as f64 * 0.08, z as f64 * 0.11);
+                280.0 + 15.0 * x.sin() * y.cos() + 4.0 * (x + z).cos() + y * z
+            })
+            .collect()
+    }
+
+    /// Check if the restored data is within the absolute error bound
+    pub fn bounded_with<T: Into<f64> + Copy>(data: &[T], restored: &[T], theta: f64) {
+        assert_eq!(data.len(), restored.len());
+        for (x, y) in data.iter().zip(restored.iter()) {
+            let (x, y): (f64, f64) = ((*x).into(), (*y).into());
+            if x.is_nan() {
+                assert!(y.is_nan());
+            } else if x != y {
+                assert!((x - y).abs() <= theta, "|{} - {}| > {}", x, y, theta);
+            }
+        }
     }
 }
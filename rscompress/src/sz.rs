@@ -0,0 +1,625 @@
+//! SZ Compression
+//!
+//! Error-bounded lossy compression of gridded floating point data following
+//! [SZ](https://szcompressor.org/) ([Di and Cappello (2016)](https://doi.org/10.1109/IPDPS.2016.11)).
+//! Each element is predicted from its already reconstructed neighbours and the
+//! prediction error is quantized linearly. Elements whose quantized prediction error
+//! is too large or violates the error bound are stored unchanged as unpredictable values.
+//! The quantization codes are Huffman coded.
+use crate::{CompressionError, Compressor};
+use log::debug;
+use rscompress_approximation::{ErrorBound, Float, LinearQuantizer};
+use rscompress_checksums::{Checksum, CRC32};
+use rscompress_coding::{Coding, Huffman};
+use rscompress_transformation::{Predictable, Predictor, Shape};
+use rscompress_types::{Array, Endianness};
+use std::marker::PhantomData;
+
+/// Magic bytes at the start of SZ compressed data
+pub const SZ_MAGIC: [u8; 4] = *b"RSSZ";
+
+/// Version of the SZ format
+pub const SZ_VERSION: u8 = 3;
+
+/// Number of quantization bins on each side of the prediction
+pub const SZ_RADIUS: u32 = 1 << 15;
+
+/// Code of unpredictable values
+const UNPREDICTABLE: u32 = 0;
+
+/// Size of the header in bytes
+const HEADER_SIZE: usize = 4 + 1 + 1 + 1 + 1 + 3 * 8 + 8 + 8 + 4 + 8 + 8 + 4;
+
+/// Trait for floating point numbers which can be compressed with SZ
+///
+/// The element type is saved in the headers by its identifier [`DataType::id`](crate::DataType::id).
+pub trait Lossy: Predictable + Float {}
+
+impl Lossy for f32 {}
+
+impl Lossy for f64 {}
+
+/// Header of SZ compressed data
+///
+/// All numbers are saved in little-endian order:
+///
+/// - magic bytes [`SZ_MAGIC`] and version [`SZ_VERSION`]
+/// - element type (`u8`, see [`DataType::id`](crate::DataType::id))
+/// - byte order of the original data (`u8`, see [`Endianness::id`]) and predictor (`u8`)
+/// - shape `nx`, `ny`, `nz` (`u64`)
+/// - absolute error bound and maximal absolute error of the data (`f64`)
+/// - quantization radius (`u32`)
+/// - number of unpredictable values and bytes of the coded quantization codes (`u64`)
+/// - CRC32 checksum of the decompressed data (`u32`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SzHeader {
+    pub dtype: u8,
+    pub endianness: Endianness,
+    pub predictor: Predictor,
+    pub shape: Shape,
+    pub theta: f64,
+    pub max_error: f64,
+    pub radius: u32,
+    pub unpredictable: u64,
+    pub coded: u64,
+    pub checksum: u32,
+}
+
+impl SzHeader {
+    /// Read the header from the start of the compressed data
+    pub fn read(source: &[u8]) -> Result<Self, CompressionError> {
+        let header = source
+            .get(..HEADER_SIZE)
+            .ok_or(CompressionError::InvalidHeader)?;
+        if header[..4] != SZ_MAGIC {
+            return Err(CompressionError::InvalidHeader);
+        }
+        if header[4] != SZ_VERSION {
+            return Err(CompressionError::UnsupportedVersion(header[4]));
+        }
+        let endianness = Endianness::from_id(header[6]).ok_or(CompressionError::InvalidHeader)?;
+        let predictor = match header[7] {
+            0 => Predictor::LastValue,
+            1 => Predictor::Plane,
+            2 => Predictor::Lorenzo,
+            _ => return Err(CompressionError::InvalidHeader),
+        };
+        let mut bytes = [0u8; 8];
+        let mut u64_at = |i: usize| {
+            bytes.copy_from_slice(&header[i..i + 8]);
+            u64::from_le_bytes(bytes)
+        };
+        let (nx, ny, nz) = (u64_at(8), u64_at(16), u64_at(24));
+        let (theta, max_error) = (f64::from_bits(u64_at(32)), f64::from_bits(u64_at(40)));
+        let (unpredictable, coded) = (u64_at(52), u64_at(60));
+        let u32_at =
+            |i: usize| u32::from_le_bytes([header[i], header[i + 1], header[i + 2], header[i + 3]]);
+        let result = SzHeader {
+            dtype: header[5],
+            endianness,
+            predictor,
+            shape: Shape::new(nx as usize, ny as usize, nz as usize),
+            theta,
+            max_error,
+            radius: u32_at(48),
+            unpredictable,
+            coded,
+            checksum: u32_at(68),
+        };
+        let len = nx.checked_mul(ny).and_then(|n| n.checked_mul(nz));
+        // every unpredictable value takes at least one byte of the body
+        let body = (source.len() - HEADER_SIZE) as u64;
+        if len.is_none()
+            || !(theta > 0.0 && theta.is_finite())
+            || max_error.is_nan()
+            || max_error > theta
+            || result.radius == 0
+            || result.radius > u32::MAX / 2
+            || unpredictable > len.unwrap_or(0)
+            || unpredictable > body
+        {
+            return Err(CompressionError::InvalidHeader);
+        }
+        Ok(result)
+    }
+    /// Append the header to the compressed data
+    pub fn write(&self, result: &mut Vec<u8>) {
+        let predictor = match self.predictor {
+            Predictor::LastValue => 0,
+            Predictor::Plane => 1,
+            Predictor::Lorenzo => 2,
+        };
+        result.extend_from_slice(&SZ_MAGIC);
+        result.extend_from_slice(&[SZ_VERSION, self.dtype, self.endianness.id(), predictor]);
+        for n in [self.shape.nx, self.shape.ny, self.shape.nz].iter() {
+            result.extend_from_slice(&(*n as u64).to_le_bytes());
+        }
+        result.extend_from_slice(&self.theta.to_le_bytes());
+        result.extend_from_slice(&self.max_error.to_le_bytes());
+        result.extend_from_slice(&self.radius.to_le_bytes());
+        result.extend_from_slice(&self.unpredictable.to_le_bytes());
+        result.extend_from_slice(&self.coded.to_le_bytes());
+        result.extend_from_slice(&self.checksum.to_le_bytes());
+    }
+}
+
+/// CRC32 checksum of the elements in little-endian order
+fn checksum<T: Lossy>(data: &[T]) -> Result<u32, CompressionError> {
+    let mut bytes = Vec::with_capacity(data.len() * T::DTYPE.size());
+    for x in data.iter() {
+        x.write_bytes(Endianness::Little, &mut bytes);
+    }
+    let mut crc = CRC32::new();
+    crc.update(&bytes);
+    crc.checksum().map_err(CompressionError::Checksum)
+}
+
+/// SZ struct to save the predictor, the shape of the grid and the error bound
+///
+/// The compressed data starts with a [`SzHeader`], followed by the Huffman coded
+/// quantization codes and the unpredictable values in little-endian order.
+/// Code `0` marks an unpredictable value, all other codes are the quantization bin
+/// of the prediction error shifted by the radius.
+/// The header contains everything needed for decompression, including the checksum
+/// of the reconstructed data. The encoder checks the error bound against this
+/// reconstruction, so a matching checksum on decompression verifies the bound.
+/// Infinities and NaN are stored as unpredictable values and restored exactly.
+///
+/// The header records the byte order of the original data, i.e. the byte order of
+/// the host for [`Sz::compress`] and the byte order of the array for [`Sz::compress_array`].
+/// Decompression returns the same values on hosts of any byte order.
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress::Sz;
+/// use rscompress_approximation::ErrorBound;
+/// use rscompress_transformation::{Predictor, Shape};
+/// let data: Vec<f32> = (0..1000).map(|i| (i as f32 * 0.01).sin()).collect();
+/// let model = Sz::new(Predictor::Lorenzo, Shape::new(1000, 1, 1), ErrorBound::Absolute(1e-3));
+/// let compressed = model.compress(&data).unwrap();
+/// assert!(compressed.len() < data.len());
+/// let restored = Sz::<f32>::decompress(&compressed).unwrap();
+/// for (x, y) in data.iter().zip(restored.iter()) {
+///     assert!((x - y).abs() <= 1e-3);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Sz<T: Lossy> {
+    predictor: Predictor,
+    shape: Shape,
+    bound: ErrorBound,
+    element: PhantomData<T>,
+}
+
+impl<T: Lossy> Sz<T> {
+    pub fn new(predictor: Predictor, shape: Shape, bound: ErrorBound) -> Self {
+        Sz {
+            predictor,
+            shape,
+            bound,
+            element: PhantomData,
+        }
+    }
+    /// Compress the data within the error bound
+    pub fn compress(&self, data: &[T]) -> Result<Vec<u8>, CompressionError> {
+        self.encode(data, Endianness::native())
+    }
+    /// Compress the elements of the array within the error bound
+    ///
+    /// The elements are read in the byte order of the array, which is recorded in the header.
+    pub fn compress_array(&self, array: &Array) -> Result<Vec<u8>, CompressionError> {
+        let data = array.to_vec::<T>().map_err(CompressionError::Type)?;
+        self.encode(&data, array.layout().endianness)
+    }
+    fn encode(&self, data: &[T], endianness: Endianness) -> Result<Vec<u8>, CompressionError> {
+        if data.len() != self.shape.len() {
+            return Err(CompressionError::ShapeMismatch(
+                self.shape.len(),
+                data.len(),
+            ));
+        }
+        let theta = self.bound.absolute(data);
+        let quantizer = LinearQuantizer::new(theta);
+        quantizer
+            .validate()
+            .map_err(CompressionError::Approximation)?;
+        let radius = SZ_RADIUS as i64;
+        let mut reconstructed = vec![T::zero(); data.len()];
+        let mut codes = Vec::with_capacity(data.len());
+        let mut unpredictable = Vec::new();
+        let mut max_error = 0.0f64;
+        for (i, x) in data.iter().enumerate() {
+            let prediction = self
+                .predictor
+                .predict(&reconstructed, &self.shape, i)
+                .to_f64();
+            let value = x.to_f64();
+            let restored = quantizer
+                .quantize(value - prediction)
+                .filter(|bin| bin.abs() < radius)
+                .map(|bin| (bin, T::from_f64(prediction + quantizer.dequantize(bin))))
+                .filter(|(_, restored)| (value - restored.to_f64()).abs() <= theta);
+            match restored {
+                Some((bin, restored)) => {
+                    max_error = max_error.max((value - restored.to_f64()).abs());
+                    codes.push((bin + radius) as u32);
+                    reconstructed[i] = restored;
+                }
+                None => {
+                    codes.push(UNPREDICTABLE);
+                    unpredictable.push(*x);
+                    reconstructed[i] = *x;
+                }
+            }
+        }
+        let coded = Huffman::new()
+            .encode(&codes)
+            .map_err(CompressionError::Coding)?;
+        let header = SzHeader {
+            dtype: T::DTYPE.id(),
+            endianness,
+            predictor: self.predictor,
+            shape: self.shape,
+            theta,
+            max_error,
+            radius: SZ_RADIUS,
+            unpredictable: unpredictable.len() as u64,
+            coded: coded.len() as u64,
+            checksum: checksum(&reconstructed)?,
+        };
+        let mut result =
+            Vec::with_capacity(HEADER_SIZE + coded.len() + unpredictable.len() * T::DTYPE.size());
+        header.write(&mut result);
+        result.extend_from_slice(&coded);
+        for x in unpredictable.iter() {
+            x.write_bytes(Endianness::Little, &mut result);
+        }
+        debug!(
+            "Compressed {} elements with {} unpredictable values into {} bytes",
+            data.len(),
+            unpredictable.len(),
+            result.len()
+        );
+        Ok(result)
+    }
+    /// Decompress the data and verify the checksum
+    ///
+    /// The predictor, shape and error bound are read from the header.
+    pub fn decompress(source: &[u8]) -> Result<Vec<T>, CompressionError> {
+        Ok(Self::decode(source)?.1)
+    }
+    /// Decompress the data into a contiguous array in the recorded byte order
+    pub fn decompress_array(source: &[u8]) -> Result<Array, CompressionError> {
+        let (header, data) = Self::decode(source)?;
+        let shape = [header.shape.nx, header.shape.ny, header.shape.nz];
+        let array = Array::from_slice(&data, &shape).map_err(CompressionError::Type)?;
+        Ok(array.to_endianness(header.endianness))
+    }
+    fn decode(source: &[u8]) -> Result<(SzHeader, Vec<T>), CompressionError> {
+        let header = SzHeader::read(source)?;
+        if header.dtype != T::DTYPE.id() {
+            return Err(CompressionError::TypeMismatch(T::DTYPE.id(), header.dtype));
+        }
+        let n = header.shape.len();
+        let body = &source[HEADER_SIZE..];
+        let coded = body
+            .get(..header.coded as usize)
+            .ok_or(CompressionError::InvalidBody)?;
+        let values = &body[coded.len()..];
+        let size = header
+            .unpredictable
+            .checked_mul(T::DTYPE.size() as u64)
+            .ok_or(CompressionError::InvalidBody)?;
+        if values.len() as u64 != size {
+            return Err(CompressionError::InvalidBody);
+        }
+        let codes = Huffman::new()
+            .decode(coded)
+            .map_err(CompressionError::Coding)?;
+        if codes.len() != n {
+            return Err(CompressionError::ShapeMismatch(n, codes.len()));
+        }
+        let quantizer = LinearQuantizer::new(header.theta);
+        let radius = header.radius as i64;
+        let mut unpredictable = values
+            .chunks_exact(T::DTYPE.size())
+            .map(|b| T::from_bytes(b, Endianness::Little));
+        let mut result = vec![T::zero(); n];
+        for (i, code) in codes.iter().enumerate() {
+            result[i] = match *code {
+                UNPREDICTABLE => unpredictable.next().ok_or(CompressionError::InvalidBody)?,
+                code => {
+                    let prediction = header.predictor.predict(&result, &header.shape, i).to_f64();
+                    let bin = code as i64 - radius;
+                    T::from_f64(prediction + quantizer.dequantize(bin))
+                }
+            };
+        }
+        if unpredictable.next().is_some() {
+            return Err(CompressionError::InvalidBody);
+        }
+        let actual = checksum(&result)?;
+        if actual != header.checksum {
+            return Err(CompressionError::ChecksumMismatch(header.checksum, actual));
+        }
+        Ok((header, result))
+    }
+}
+
+/// Implementation of the Compressor trait for SZ
+impl<T: Lossy> Compressor<T> for Sz<T> {
+    fn compress(&self, data: &[T]) -> Result<Vec<u8>, CompressionError> {
+        Sz::compress(self, data)
+    }
+    fn decompress(&self, source: &[u8]) -> Result<Vec<T>, CompressionError> {
+        Sz::decompress(source)
+    }
+    fn compress_array(&self, array: &Array) -> Result<Vec<u8>, CompressionError> {
+        Sz::compress_array(self, array)
+    }
+    fn decompress_array(&self, source: &[u8]) -> Result<Array, CompressionError> {
+        Sz::<T>::decompress_array(source)
+    }
+    fn slice(&self) -> (usize, usize) {
+        (self.shape.nx, self.shape.ny)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{bounded_with, normal_f32, smooth_field};
+    use crate::{DataType, Layout};
+
+    fn roundtrip_with<T: Lossy + Into<f64>>(model: &Sz<T>, data: &[T], theta: f64) -> Vec<u8> {
+        let compressed = model.compress(data).unwrap();
+        let restored = Sz::<T>::decompress(&compressed).unwrap();
+        bounded_with(data, &restored, theta);
+        let header = SzHeader::read(&compressed).unwrap();
+        assert!(header.max_error <= theta);
+        compressed
+    }
+
+    #[test]
+    fn test_smooth_field() {
+        let (nx, ny, nz) = (64, 32, 16);
+        let data = smooth_field(nx, ny, nz);
+        let shape = Shape::new(nx, ny, nz);
+        for predictor in [Predictor::LastValue, Predictor::Plane, Predictor::Lorenzo].iter() {
+            for theta in [1e-6, 1e-3, 0.1].iter() {
+                let model = Sz::new(*predictor, shape, ErrorBound::Absolute(*theta));
+                let compressed = roundtrip_with(&model, &data, *theta);
+                if *theta >= 1e-3 {
+                    assert!(compressed.len() * 4 < data.len() * 8);
+                }
+            }
+        }
+        let coarse = Sz::new(Predictor::Lorenzo, shape, ErrorBound::Absolute(0.1));
+        let fine = Sz::new(Predictor::Lorenzo, shape, ErrorBound::Absolute(1e-6));
+        let last = Sz::new(Predictor::LastValue, shape, ErrorBound::Absolute(1e-6));
+        let fine = fine.compress(&data).unwrap().len();
+        assert!(coarse.compress(&data).unwrap().len() < fine);
+        // the prediction errors of the last value exceed the quantization radius
+        assert!(fine * 4 < last.compress(&data).unwrap().len());
+    }
+
+    #[test]
+    fn test_normal_testdata() {
+        let data = &normal_f32()[..100_000];
+        let shape = Shape::new(100, 1_000, 1);
+        for theta in [1e-4, 0.5, 25.6].iter() {
+            let model = Sz::new(Predictor::LastValue, shape, ErrorBound::Absolute(*theta));
+            roundtrip_with(&model, data, *theta);
+        }
+        // f32 has a precision of 2^-16 around 256, values are unpredictable
+        let model = Sz::new(Predictor::LastValue, shape, ErrorBound::Absolute(1e-7));
+        let compressed = roundtrip_with(&model, data, 1e-7);
+        assert!(SzHeader::read(&compressed).unwrap().unpredictable > 99_900);
+    }
+
+    #[test]
+    fn test_relative_bound() {
+        let data: Vec<f32> = smooth_field(100, 100, 1)
+            .iter()
+            .map(|x| *x as f32)
+            .collect();
+        let model = Sz::new(
+            Predictor::Plane,
+            Shape::new(100, 100, 1),
+            ErrorBound::Relative(1e-4),
+        );
+        let theta = ErrorBound::Relative(1e-4).absolute(&data);
+        assert!(theta > 1e-3 && theta < 1e-2);
+        let compressed = roundtrip_with(&model, &data, theta);
+        assert_eq!(SzHeader::read(&compressed).unwrap().theta, theta);
+        let constant = [3.5f64; 64];
+        let model = Sz::new(
+            Predictor::Lorenzo,
+            Shape::new(4, 4, 4),
+            ErrorBound::Relative(0.1),
+        );
+        let restored = Sz::<f64>::decompress(&model.compress(&constant).unwrap()).unwrap();
+        assert_eq!(restored, constant);
+    }
+
+    #[test]
+    fn test_special_values() {
+        let data = [
+            1.0f64,
+            f64::NAN,
+            2.0,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            3.0,
+            1e300,
+            -1e300,
+            0.0,
+        ];
+        let model = Sz::new(
+            Predictor::LastValue,
+            Shape::new(9, 1, 1),
+            ErrorBound::Absolute(0.01),
+        );
+        let compressed = roundtrip_with(&model, &data, 0.01);
+        assert!(SzHeader::read(&compressed).unwrap().unpredictable >= 5);
+        roundtrip_with(
+            &Sz::new(
+                Predictor::Lorenzo,
+                Shape::new(0, 4, 4),
+                ErrorBound::Absolute(0.1),
+            ),
+            &[] as &[f32],
+            0.1,
+        );
+    }
+
+    #[test]
+    fn test_big_endian_fixture() {
+        let data = smooth_field(32, 16, 4);
+        let model = Sz::<f64>::new(
+            Predictor::Lorenzo,
+            Shape::new(32, 16, 4),
+            ErrorBound::Absolute(1e-3),
+        );
+        // data written on a big-endian host
+        let bytes: Vec<u8> = data.iter().flat_map(|x| x.to_be_bytes()).collect();
+        let mut layout = Layout::new(DataType::F64, &[32, 16, 4]);
+        layout.endianness = Endianness::Big;
+        let compressed = model
+            .compress_array(&Array::with_layout(layout, bytes.clone()).unwrap())
+            .unwrap();
+        assert_eq!(
+            SzHeader::read(&compressed).unwrap().endianness,
+            Endianness::Big
+        );
+        bounded_with(&data, &Sz::<f64>::decompress(&compressed).unwrap(), 1e-3);
+        // only the recorded byte order differs from the little-endian data
+        let little = model
+            .compress_array(&Array::from_slice(&data, &[32, 16, 4]).unwrap())
+            .unwrap();
+        assert_eq!(compressed[..6], little[..6]);
+        assert_eq!(compressed[7..], little[7..]);
+        let restored = Sz::<f64>::decompress_array(&compressed).unwrap();
+        assert_eq!(restored.layout().endianness, Endianness::Big);
+        assert_eq!(restored.as_bytes().len(), bytes.len());
+        bounded_with(&data, &restored.to_vec::<f64>().unwrap(), 1e-3);
+        let single = Array::from_slice(&vec![0f32; 2048], &[32, 16, 4]).unwrap();
+        assert!(matches!(
+            model.compress_array(&single),
+            Err(CompressionError::Type(_))
+        ));
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let data = smooth_field(64, 64, 2);
+        let model = Sz::new(
+            Predictor::Lorenzo,
+            Shape::new(64, 64, 2),
+            ErrorBound::Absolute(1e-2),
+        );
+        let evaluation = model.evaluate(&data, true).unwrap();
+        assert_eq!(evaluation.original, 64 * 64 * 2 * 8);
+        assert_eq!(evaluation.compressed, model.compress(&data).unwrap().len());
+        assert!(evaluation.ratio > 8.0);
+        let errors = evaluation.errors.unwrap();
+        assert!(errors.max_abs_error <= 1e-2);
+        assert!(errors.ssim.unwrap() > 0.99);
+        assert!(evaluation.to_string().contains("ratio"));
+        assert_eq!(model.evaluate(&data, false).unwrap().errors, None);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let data = smooth_field(16, 16, 1);
+        let model = Sz::new(
+            Predictor::Plane,
+            Shape::new(16, 16, 1),
+            ErrorBound::Absolute(1e-2),
+        );
+        assert!(matches!(
+            model.compress(&data[1..]),
+            Err(CompressionError::ShapeMismatch(256, 255))
+        ));
+        let invalid = Sz::new(
+            Predictor::Plane,
+            Shape::new(16, 16, 1),
+            ErrorBound::Absolute(-1.0),
+        );
+        assert!(matches!(
+            invalid.compress(&data),
+            Err(CompressionError::Approximation(_))
+        ));
+        let compressed = model.compress(&data).unwrap();
+        assert!(matches!(
+            Sz::<f32>::decompress(&compressed),
+            Err(CompressionError::TypeMismatch(8, 9))
+        ));
+        assert!(matches!(
+            Sz::<f64>::decompress(&compressed[..HEADER_SIZE - 1]),
+            Err(CompressionError::InvalidHeader)
+        ));
+        let mut invalid = compressed.clone();
+        invalid[0] = b'X';
+        assert!(matches!(
+            Sz::<f64>::decompress(&invalid),
+            Err(CompressionError::InvalidHeader)
+        ));
+        invalid = compressed.clone();
+        invalid[6] = 2;
+        assert!(matches!(
+            Sz::<f64>::decompress(&invalid),
+            Err(CompressionError::InvalidHeader)
+        ));
+        invalid = compressed.clone();
+        invalid[4] = SZ_VERSION + 1;
+        assert!(matches!(
+            Sz::<f64>::decompress(&invalid),
+            Err(CompressionError::UnsupportedVersion(_))
+        ));
+        assert!(matches!(
+            Sz::<f64>::decompress(&compressed[..compressed.len() - 1]),
+            Err(CompressionError::InvalidBody)
+        ));
+        // a different error bound changes the reconstruction
+        invalid = compressed.clone();
+        invalid[32..40].copy_from_slice(&5e-3f64.to_le_bytes());
+        invalid[40..48].copy_from_slice(&0f64.to_le_bytes());
+        assert!(matches!(
+            Sz::<f64>::decompress(&invalid),
+            Err(CompressionError::ChecksumMismatch(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_crafted_header() {
+        let data = smooth_field(16, 16, 1);
+        let model = Sz::new(
+            Predictor::Plane,
+            Shape::new(16, 16, 1),
+            ErrorBound::Absolute(1e-2),
+        );
+        let compressed = model.compress(&data).unwrap();
+        let mut crafted = compressed.clone();
+        crafted[8..16].copy_from_slice(&(1u64 << 62).to_le_bytes());
+        crafted[16..24].copy_from_slice(&1u64.to_le_bytes());
+        crafted[52..60].copy_from_slice(&(1u64 << 62).to_le_bytes());
+        assert!(matches!(
+            Sz::<f64>::decompress(&crafted),
+            Err(CompressionError::InvalidHeader)
+        ));
+        let body = (compressed.len() - HEADER_SIZE) as u64;
+        crafted = compressed.clone();
+        crafted[52..60].copy_from_slice(&(body + 1).to_le_bytes());
+        assert!(matches!(
+            Sz::<f64>::decompress(&crafted),
+            Err(CompressionError::InvalidHeader)
+        ));
+        crafted[52..60].copy_from_slice(&body.to_le_bytes());
+        assert!(matches!(
+            Sz::<f64>::decompress(&crafted),
+            Err(CompressionError::InvalidBody)
+        ));
+    }
+}
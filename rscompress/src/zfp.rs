@@ -0,0 +1,915 @@
+//! ZFP Compression
+//!
+//! Lossy transform coding of gridded floating point data in one to four dimensions
+//! following [ZFP](https://zfp.io/) ([Lindstrom (2014)](https://doi.org/10.1109/TVCG.2014.2346458)).
+//! The grid is partitioned into blocks of `4^d` elements, which are compressed independently:
+//!
+//! 1. Conversion to block-floating-point integers relative to the largest exponent
+//! 2. Decorrelation with the nearly orthogonal lifted transform of ZFP along each dimension
+//! 3. Reordering of the coefficients by sequency and conversion to negabinary
+//! 4. Embedded coding of the bit planes starting at the most significant one
+//!
+//! The embedded coding can be stopped after any bit, which allows a fixed size,
+//! a fixed number of bit planes or a fixed accuracy of each block.
+use crate::{CompressionError, Compressor, Lossy};
+use log::debug;
+use rscompress_coding::{BitReader, BitWriter, CodingError};
+use rscompress_types::{Array, Endianness};
+use std::marker::PhantomData;
+
+/// Magic bytes at the start of ZFP compressed data
+pub const ZFP_MAGIC: [u8; 4] = *b"RSZF";
+
+/// Version of the ZFP format
+pub const ZFP_VERSION: u8 = 3;
+
+/// Number of bits of the block-floating-point integers
+const INTPREC: u32 = 64;
+
+/// Mask for the conversion between two's complement and negabinary
+const NBMASK: u64 = 0xaaaa_aaaa_aaaa_aaaa;
+
+/// Modes of the ZFP compression
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ZfpMode {
+    /// Fixed number of bits per element, which allows random access to the blocks
+    FixedRate(f64),
+    /// Fixed number of encoded bit planes per block (at most 64)
+    FixedPrecision(u32),
+    /// Absolute error tolerance
+    FixedAccuracy(f64),
+}
+
+impl ZfpMode {
+    fn id(&self) -> (u8, f64) {
+        match *self {
+            ZfpMode::FixedRate(rate) => (0, rate),
+            ZfpMode::FixedPrecision(precision) => (1, precision as f64),
+            ZfpMode::FixedAccuracy(tolerance) => (2, tolerance),
+        }
+    }
+    fn from_id(id: u8, parameter: f64) -> Option<Self> {
+        match id {
+            0 => Some(ZfpMode::FixedRate(parameter)),
+            1 if parameter.fract() == 0.0 && parameter >= 0.0 && parameter <= INTPREC as f64 => {
+                Some(ZfpMode::FixedPrecision(parameter as u32))
+            }
+            2 => Some(ZfpMode::FixedAccuracy(parameter)),
+            _ => None,
+        }
+    }
+}
+
+/// Coding parameters of the blocks for an element type and dimensionality
+#[derive(Debug, Clone, Copy)]
+struct Parameters {
+    dims: usize,
+    /// Number of bits of the exponent and its bias
+    ebits: u32,
+    ebias: i32,
+    /// Maximal bits and bit planes per block, and the smallest encoded exponent
+    maxbits: usize,
+    maxprec: u32,
+    minexp: i32,
+    /// Pad each block to `maxbits`
+    fixed: bool,
+}
+
+impl Parameters {
+    fn new<T: Lossy>(mode: ZfpMode, dims: usize) -> Result<Self, CompressionError> {
+        let ebits = T::BITS - T::MANTISSA_BITS - 1;
+        let size = 1usize << (2 * dims);
+        let mut result = Parameters {
+            dims,
+            ebits,
+            ebias: (1 << (ebits - 1)) - 1,
+            maxbits: usize::MAX,
+            maxprec: INTPREC,
+            minexp: i32::MIN,
+            fixed: false,
+        };
+        match mode {
+            ZfpMode::FixedRate(rate) => {
+                let maxbits = (rate * size as f64).floor();
+                if !(maxbits >= 1.0 + ebits as f64 && rate <= INTPREC as f64) {
+                    return Err(CompressionError::InvalidParameter);
+                }
+                result.maxbits = maxbits as usize;
+                result.fixed = true;
+            }
+            ZfpMode::FixedPrecision(precision) => {
+                if precision == 0 || precision > INTPREC {
+                    return Err(CompressionError::InvalidParameter);
+                }
+                result.maxprec = precision;
+            }
+            ZfpMode::FixedAccuracy(tolerance) => {
+                if !(tolerance > 0.0 && tolerance.is_finite()) {
+                    return Err(CompressionError::InvalidParameter);
+                }
+                result.minexp = exponent(tolerance) - 1;
+            }
+        }
+        Ok(result)
+    }
+    /// Number of encoded bit planes of a block with the maximal exponent `emax`
+    fn precision(&self, emax: i32) -> u32 {
+        let planes = emax as i64 - self.minexp as i64 + 2 * (self.dims as i64 + 1);
+        planes.clamp(0, self.maxprec as i64) as u32
+    }
+}
+
+/// Exponent `e` of a finite non-zero number with `x = m * 2^e` and `0.5 <= |m| < 1`
+fn exponent(x: f64) -> i32 {
+    let biased = ((x.to_bits() >> 52) & 0x7ff) as i32;
+    if biased == 0 {
+        exponent(x * 2f64.powi(64)) - 64
+    } else {
+        biased - 1022
+    }
+}
+
+/// Multiply `x` with `2^e` without intermediate overflow of the power
+fn ldexp(mut x: f64, mut e: i32) -> f64 {
+    while e > 1000 {
+        x *= 2f64.powi(1000);
+        e -= 1000;
+    }
+    while e < -1000 {
+        x *= 2f64.powi(-1000);
+        e += 1000;
+    }
+    x * 2f64.powi(e)
+}
+
+/// Forward lifted transform of four coefficients
+fn forward_lift(p: &mut [i64], offset: usize, stride: usize) {
+    let (i, j, k, l) = (
+        offset,
+        offset + stride,
+        offset + 2 * stride,
+        offset + 3 * stride,
+    );
+    let (mut x, mut y, mut z, mut w) = (p[i], p[j], p[k], p[l]);
+    x = x.wrapping_add(w) >> 1;
+    w = w.wrapping_sub(x);
+    z = z.wrapping_add(y) >> 1;
+    y = y.wrapping_sub(z);
+    x = x.wrapping_add(z) >> 1;
+    z = z.wrapping_sub(x);
+    w = w.wrapping_add(y) >> 1;
+    y = y.wrapping_sub(w);
+    w = w.wrapping_add(y >> 1);
+    y = y.wrapping_sub(w >> 1);
+    p[i] = x;
+    p[j] = y;
+    p[k] = z;
+    p[l] = w;
+}
+
+/// Inverse lifted transform of four coefficients
+fn inverse_lift(p: &mut [i64], offset: usize, stride: usize) {
+    let (i, j, k, l) = (
+        offset,
+        offset + stride,
+        offset + 2 * stride,
+        offset + 3 * stride,
+    );
+    let (mut x, mut y, mut z, mut w) = (p[i], p[j], p[k], p[l]);
+    y = y.wrapping_add(w >> 1);
+    w = w.wrapping_sub(y >> 1);
+    y = y.wrapping_add(w);
+    w = (w << 1).wrapping_sub(y);
+    z = z.wrapping_add(x);
+    x = (x << 1).wrapping_sub(z);
+    y = y.wrapping_add(z);
+    z = (z << 1).wrapping_sub(y);
+    w = w.wrapping_add(x);
+    x = (x << 1).wrapping_sub(w);
+    p[i] = x;
+    p[j] = y;
+    p[k] = z;
+    p[l] = w;
+}
+
+/// Offsets of all lines of a block along dimension `dim`
+fn lines(dims: usize, dim: usize) -> impl Iterator<Item = usize> {
+    (0..1usize << (2 * dims)).filter(move |j| (j >> (2 * dim)) & 3 == 0)
+}
+
+/// Coefficients of a block ordered by sequency, i.e. the sum of their local coordinates
+fn sequency(dims: usize) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..1usize << (2 * dims)).collect();
+    order.sort_by_key(|j| ((0..dims).map(|k| (j >> (2 * k)) & 3).sum::<usize>(), *j));
+    order
+}
+
+/// Embedded coding of the bit planes with group testing
+///
+/// The bits of the coefficients, which were significant in previous bit planes, are
+/// written verbatim. The remaining bits are coded by unary run lengths.
+fn encode_ints(writer: &mut BitWriter, mut bits: usize, maxprec: u32, data: &[u64]) {
+    let size = data.len();
+    let mut n = 0;
+    for k in (INTPREC - maxprec..INTPREC).rev() {
+        if bits == 0 {
+            break;
+        }
+        let bit = |i: usize| (data[i] >> k) & 1 == 1;
+        let m = n.min(bits);
+        bits -= m;
+        for i in 0..m {
+            writer.write_bit(bit(i));
+        }
+        while bits > 0 && n < size {
+            bits -= 1;
+            let any = (n..size).any(bit);
+            writer.write_bit(any);
+            if !any {
+                break;
+            }
+            while bits > 0 && n < size - 1 {
+                bits -= 1;
+                writer.write_bit(bit(n));
+                if bit(n) {
+                    break;
+                }
+                n += 1;
+            }
+            n += 1;
+        }
+    }
+}
+
+/// Decoding of the embedded bit planes (see [`encode_ints`])
+fn decode_ints(
+    reader: &mut BitReader,
+    mut bits: usize,
+    maxprec: u32,
+    size: usize,
+) -> Result<Vec<u64>, CodingError> {
+    let mut data = vec![0u64; size];
+    let mut n = 0;
+    for k in (INTPREC - maxprec..INTPREC).rev() {
+        if bits == 0 {
+            break;
+        }
+        let m = n.min(bits);
+        bits -= m;
+        for value in data[..m].iter_mut() {
+            *value |= (reader.read_bit()? as u64) << k;
+        }
+        while bits > 0 && n < size {
+            bits -= 1;
+            if !reader.read_bit()? {
+                break;
+            }
+            while bits > 0 && n < size - 1 {
+                bits -= 1;
+                if reader.read_bit()? {
+                    break;
+                }
+                n += 1;
+            }
+            data[n] |= 1 << k;
+            n += 1;
+        }
+    }
+    Ok(data)
+}
+
+/// Encode a single block
+fn encode_block(writer: &mut BitWriter, block: &[f64], params: &Parameters, order: &[usize]) {
+    let start = writer.bits_written();
+    let maxabs = block.iter().fold(0.0f64, |m, x| m.max(x.abs()));
+    let emax = match maxabs {
+        m if m > 0.0 => exponent(m).max(1 - params.ebias),
+        _ => 0,
+    };
+    let maxprec = params.precision(emax);
+    if maxabs == 0.0 || maxprec == 0 {
+        writer.write_bit(false);
+    } else {
+        writer.write_bit(true);
+        writer.write((emax + params.ebias) as u64, params.ebits);
+        let mut ints: Vec<i64> = block
+            .iter()
+            .map(|x| ldexp(*x, INTPREC as i32 - 2 - emax) as i64)
+            .collect();
+        for dim in 0..params.dims {
+            for offset in lines(params.dims, dim) {
+                forward_lift(&mut ints, offset, 1 << (2 * dim));
+            }
+        }
+        let coefficients: Vec<u64> = order
+            .iter()
+            .map(|j| (ints[*j] as u64).wrapping_add(NBMASK) ^ NBMASK)
+            .collect();
+        let bits = params.maxbits.saturating_sub(1 + params.ebits as usize);
+        encode_ints(writer, bits, maxprec, &coefficients);
+    }
+    if params.fixed {
+        let used = writer.bits_written() - start;
+        for _ in used..params.maxbits {
+            writer.write_bit(false);
+        }
+    }
+}
+
+/// Decode a single block
+fn decode_block(
+    reader: &mut BitReader,
+    params: &Parameters,
+    order: &[usize],
+) -> Result<Vec<f64>, CodingError> {
+    let start = reader.bits_read();
+    let size = order.len();
+    let mut block = vec![0.0f64; size];
+    if reader.read_bit()? {
+        let emax = reader.read(params.ebits)? as i32 - params.ebias;
+        let maxprec = params.precision(emax);
+        let bits = params.maxbits.saturating_sub(1 + params.ebits as usize);
+        let coefficients = decode_ints(reader, bits, maxprec, size)?;
+        let mut ints = vec![0i64; size];
+        for (j, u) in order.iter().zip(coefficients.iter()) {
+            ints[*j] = (u ^ NBMASK).wrapping_sub(NBMASK) as i64;
+        }
+        for dim in (0..params.dims).rev() {
+            for offset in lines(params.dims, dim) {
+                inverse_lift(&mut ints, offset, 1 << (2 * dim));
+            }
+        }
+        for (x, i) in block.iter_mut().zip(ints.iter()) {
+            *x = ldexp(*i as f64, emax - (INTPREC as i32 - 2));
+        }
+    }
+    if params.fixed {
+        reader.skip((start + params.maxbits).saturating_sub(reader.bits_read()));
+    }
+    Ok(block)
+}
+
+/// Grid of blocks with the element indices of each block
+#[derive(Debug, Clone)]
+struct Blocks {
+    dims: Vec<usize>,
+    strides: Vec<usize>,
+    counts: Vec<usize>,
+}
+
+impl Blocks {
+    fn new(dims: &[usize]) -> Self {
+        let strides = dims
+            .iter()
+            .scan(1usize, |s, n| {
+                let stride = *s;
+                *s = s.saturating_mul(*n);
+                Some(stride)
+            })
+            .collect();
+        Blocks {
+            dims: dims.to_vec(),
+            strides,
+            counts: dims.iter().map(|n| n.div_ceil(4)).collect(),
+        }
+    }
+    /// Number of blocks
+    fn len(&self) -> usize {
+        self.counts.iter().product()
+    }
+    /// Element index of each value of the block, or `None` for padding
+    ///
+    /// Values outside of the grid repeat the last element along each dimension.
+    fn indices(&self, block: usize) -> Vec<(usize, bool)> {
+        let d = self.dims.len();
+        let mut origin = Vec::with_capacity(d);
+        let mut rest = block;
+        for count in self.counts.iter() {
+            origin.push(4 * (rest % count));
+            rest /= count;
+        }
+        (0..1usize << (2 * d))
+            .map(|j| {
+                let mut index = 0;
+                let mut inside = true;
+                for (k, start) in origin.iter().enumerate() {
+                    let coordinate = start + ((j >> (2 * k)) & 3);
+                    inside &= coordinate < self.dims[k];
+                    index += coordinate.min(self.dims[k] - 1) * self.strides[k];
+                }
+                (index, inside)
+            })
+            .collect()
+    }
+}
+
+/// Header of ZFP compressed data
+///
+/// All numbers are saved in little-endian order:
+///
+/// - magic bytes [`ZFP_MAGIC`] and version [`ZFP_VERSION`]
+/// - element type (`u8`, see [`DataType::id`](crate::DataType::id))
+/// - byte order of the original data (`u8`, see [`Endianness::id`])
+/// - mode (`u8`) and its parameter (`f64`)
+/// - number of dimensions (`u8`) and their sizes, fastest varying first (`u64`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZfpHeader {
+    pub dtype: u8,
+    pub endianness: Endianness,
+    pub mode: ZfpMode,
+    pub dims: Vec<usize>,
+}
+
+impl ZfpHeader {
+    /// Size of the header in bytes
+    pub fn size(&self) -> usize {
+        4 + 1 + 1 + 1 + 1 + 8 + 1 + 8 * self.dims.len()
+    }
+    /// Read the header from the start of the compressed data
+    pub fn read(source: &[u8]) -> Result<Self, CompressionError> {
+        let header = source.get(..17).ok_or(CompressionError::InvalidHeader)?;
+        if header[..4] != ZFP_MAGIC {
+            return Err(CompressionError::InvalidHeader);
+        }
+        if header[4] != ZFP_VERSION {
+            return Err(CompressionError::UnsupportedVersion(header[4]));
+        }
+        let u64_at = |i: usize| {
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&source[i..i + 8]);
+            u64::from_le_bytes(bytes)
+        };
+        let endianness = Endianness::from_id(header[6]).ok_or(CompressionError::InvalidHeader)?;
+        let mode = ZfpMode::from_id(header[7], f64::from_bits(u64_at(8)))
+            .ok_or(CompressionError::InvalidHeader)?;
+        let d = header[16] as usize;
+        if !(1..=4).contains(&d) || source.len() < 17 + 8 * d {
+            return Err(CompressionError::InvalidHeader);
+        }
+        let dims: Vec<usize> = (0..d).map(|k| u64_at(17 + 8 * k) as usize).collect();
+        if dims
+            .iter()
+            .try_fold(1usize, |n, d| n.checked_mul(*d))
+            .is_none()
+        {
+            return Err(CompressionError::InvalidHeader);
+        }
+        Ok(ZfpHeader {
+            dtype: header[5],
+            endianness,
+            mode,
+            dims,
+        })
+    }
+    /// Append the header to the compressed data
+    pub fn write(&self, result: &mut Vec<u8>) {
+        let (id, parameter) = self.mode.id();
+        result.extend_from_slice(&ZFP_MAGIC);
+        result.extend_from_slice(&[ZFP_VERSION, self.dtype, self.endianness.id(), id]);
+        result.extend_from_slice(&parameter.to_le_bytes());
+        result.push(self.dims.len() as u8);
+        for n in self.dims.iter() {
+            result.extend_from_slice(&(*n as u64).to_le_bytes());
+        }
+    }
+}
+
+/// ZFP struct to save the mode and the sizes of the dimensions
+///
+/// The dimensions are ordered with the fastest varying first (compare
+/// [`Shape`](rscompress_transformation::Shape)). The compressed data starts with
+/// a [`ZfpHeader`], followed by the bit stream of the blocks in memory order.
+/// Partial blocks at the border of the grid are padded by repeating the last element.
+///
+/// - [`ZfpMode::FixedRate`] pads each block to the same number of bits, so single
+///   blocks can be decompressed with [`Zfp::decompress_block`]. The rate must cover
+///   at least the header of the block, i.e. `1 + exponent bits` per block.
+/// - [`ZfpMode::FixedPrecision`] encodes the same number of bit planes for each block,
+///   which bounds the error relative to the largest value of the block.
+/// - [`ZfpMode::FixedAccuracy`] encodes the bit planes necessary to keep the
+///   absolute error below the tolerance.
+///
+/// Infinities and NaN can not be represented by the block-floating-point integers.
+/// The header records the byte order of the original data (compare [`Sz`](crate::Sz)).
+///
+/// # Example
+///
+/// ```rust
+/// use rscompress::{Zfp, ZfpMode};
+/// let data: Vec<f64> = (0..64 * 64).map(|i| ((i % 64) as f64 * 0.1).sin() + (i / 64) as f64).collect();
+/// let model = Zfp::new(ZfpMode::FixedAccuracy(1e-3), &[64, 64]);
+/// let compressed = model.compress(&data).unwrap();
+/// assert!(compressed.len() < data.len() * 2);
+/// let restored = Zfp::<f64>::decompress(&compressed).unwrap();
+/// for (x, y) in data.iter().zip(restored.iter()) {
+///     assert!((x - y).abs() <= 1e-3);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Zfp<T: Lossy> {
+    mode: ZfpMode,
+    dims: Vec<usize>,
+    element: PhantomData<T>,
+}
+
+impl<T: Lossy> Zfp<T> {
+    pub fn new(mode: ZfpMode, dims: &[usize]) -> Self {
+        Zfp {
+            mode,
+            dims: dims.to_vec(),
+            element: PhantomData,
+        }
+    }
+    /// Compress the data with the mode
+    pub fn compress(&self, data: &[T]) -> Result<Vec<u8>, CompressionError> {
+        self.encode(data, Endianness::native())
+    }
+    /// Compress the elements of the array with the mode
+    ///
+    /// The elements are read in the byte order of the array, which is recorded in the header.
+    pub fn compress_array(&self, array: &Array) -> Result<Vec<u8>, CompressionError> {
+        let data = array.to_vec::<T>().map_err(CompressionError::Type)?;
+        self.encode(&data, array.layout().endianness)
+    }
+    fn encode(&self, data: &[T], endianness: Endianness) -> Result<Vec<u8>, CompressionError> {
+        if !(1..=4).contains(&self.dims.len()) {
+            return Err(CompressionError::InvalidParameter);
+        }
+        let len = self
+            .dims
+            .iter()
+            .try_fold(1usize, |n, d| n.checked_mul(*d))
+            .ok_or(CompressionError::InvalidParameter)?;
+        if data.len() != len {
+            return Err(CompressionError::ShapeMismatch(len, data.len()));
+        }
+        if let Some(i) = data.iter().position(|x| !x.to_f64().is_finite()) {
+            return Err(CompressionError::NonFinite(i));
+        }
+        let params = Parameters::new::<T>(self.mode, self.dims.len())?;
+        let order = sequency(self.dims.len());
+        let blocks = Blocks::new(&self.dims);
+        let mut writer = BitWriter::new();
+        for b in 0..blocks.len() {
+            let block: Vec<f64> = blocks
+                .indices(b)
+                .iter()
+                .map(|(i, _)| data[*i].to_f64())
+                .collect();
+            encode_block(&mut writer, &block, &params, &order);
+        }
+        let header = ZfpHeader {
+            dtype: T::DTYPE.id(),
+            endianness,
+            mode: self.mode,
+            dims: self.dims.clone(),
+        };
+        let mut result = Vec::with_capacity(header.size() + writer.bits_written().div_ceil(8));
+        header.write(&mut result);
+        result.extend_from_slice(&writer.finish());
+        debug!(
+            "Compressed {} elements in {} blocks into {} bytes",
+            data.len(),
+            blocks.len(),
+            result.len()
+        );
+        Ok(result)
+    }
+    /// Read the header and prepare the decoding of the blocks
+    fn prepare(source: &[u8]) -> Result<(ZfpHeader, Parameters, Blocks), CompressionError> {
+        let header = ZfpHeader::read(source)?;
+        if header.dtype != T::DTYPE.id() {
+            return Err(CompressionError::TypeMismatch(T::DTYPE.id(), header.dtype));
+        }
+        let params = Parameters::new::<T>(header.mode, header.dims.len())
+            .map_err(|_| CompressionError::InvalidHeader)?;
+        let blocks = Blocks::new(&header.dims);
+        // each block takes at least one bit
+        let body = (source.len() - header.size()) as u128 * 8;
+        let needed = blocks.len() as u128
+            * if params.fixed {
+                params.maxbits as u128
+            } else {
+                1
+            };
+        if needed > body {
+            return Err(CompressionError::InvalidBody);
+        }
+        Ok((header, params, blocks))
+    }
+    /// Decompress the data with the mode read from the header
+    pub fn decompress(source: &[u8]) -> Result<Vec<T>, CompressionError> {
+        Ok(Self::decode(source)?.1)
+    }
+    /// Decompress the data into a contiguous array in the recorded byte order
+    pub fn decompress_array(source: &[u8]) -> Result<Array, CompressionError> {
+        let (header, data) = Self::decode(source)?;
+        let array = Array::from_slice(&data, &header.dims).map_err(CompressionError::Type)?;
+        Ok(array.to_endianness(header.endianness))
+    }
+    fn decode(source: &[u8]) -> Result<(ZfpHeader, Vec<T>), CompressionError> {
+        let (header, params, blocks) = Self::prepare(source)?;
+        let order = sequency(header.dims.len());
+        let mut reader = BitReader::new(&source[header.size()..]);
+        let mut result = vec![T::zero(); header.dims.iter().product()];
+        for b in 0..blocks.len() {
+            let block =
+                decode_block(&mut reader, &params, &order).map_err(CompressionError::Coding)?;
+            for ((i, inside), x) in blocks.indices(b).iter().zip(block.iter()) {
+                if *inside {
+                    result[*i] = T::from_f64(*x);
+                }
+            }
+        }
+        Ok((header, result))
+    }
+    /// Decompress a single block of data compressed with a fixed rate
+    ///
+    /// The elements of the block inside the grid are returned in memory order.
+    /// Blocks are numbered in memory order with the fastest varying dimension first.
+    pub fn decompress_block(source: &[u8], index: usize) -> Result<Vec<T>, CompressionError> {
+        let (header, params, blocks) = Self::prepare(source)?;
+        if !params.fixed || index >= blocks.len() {
+            return Err(CompressionError::InvalidParameter);
+        }
+        let order = sequency(header.dims.len());
+        let mut reader = BitReader::new(&source[header.size()..]);
+        reader.skip(index * params.maxbits);
+        let block = decode_block(&mut reader, &params, &order).map_err(CompressionError::Coding)?;
+        let result = blocks
+            .indices(index)
+            .iter()
+            .zip(block.iter())
+            .filter(|((_, inside), _)| *inside)
+            .map(|(_, x)| T::from_f64(*x))
+            .collect();
+        Ok(result)
+    }
+}
+
+/// Implementation of the Compressor trait for ZFP
+impl<T: Lossy> Compressor<T> for Zfp<T> {
+    fn compress(&self, data: &[T]) -> Result<Vec<u8>, CompressionError> {
+        Zfp::compress(self, data)
+    }
+    fn decompress(&self, source: &[u8]) -> Result<Vec<T>, CompressionError> {
+        Zfp::decompress(source)
+    }
+    fn compress_array(&self, array: &Array) -> Result<Vec<u8>, CompressionError> {
+        Zfp::compress_array(self, array)
+    }
+    fn decompress_array(&self, source: &[u8]) -> Result<Array, CompressionError> {
+        Zfp::<T>::decompress_array(source)
+    }
+    fn slice(&self) -> (usize, usize) {
+        let size = |k: usize| self.dims.get(k).copied().unwrap_or(1);
+        (size(0), size(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{bounded_with, normal_f32, smooth_field};
+    use crate::{DataType, Layout};
+
+    fn roundtrip_with<T: Lossy + Into<f64>>(model: &Zfp<T>, data: &[T]) -> (Vec<u8>, Vec<T>) {
+        let compressed = model.compress(data).unwrap();
+        let restored = Zfp::<T>::decompress(&compressed).unwrap();
+        assert_eq!(restored.len(), data.len());
+        (compressed, restored)
+    }
+
+    #[test]
+    fn test_lift() {
+        let mut p = [17i64 << 40, -5 << 40, 3 << 40, 1 << 40];
+        let original = p;
+        forward_lift(&mut p, 0, 1);
+        inverse_lift(&mut p, 0, 1);
+        assert_eq!(p, original);
+        let mut constant = [1i64 << 50; 4];
+        forward_lift(&mut constant, 0, 1);
+        assert_eq!(constant, [1 << 50, 0, 0, 0]);
+        assert_eq!(sequency(2)[..4], [0, 1, 4, 2]);
+        assert_eq!(exponent(1.0), 1);
+        assert_eq!(exponent(0.75), 0);
+        assert_eq!(exponent(f64::from_bits(1)), -1073);
+    }
+
+    #[test]
+    fn test_fixed_accuracy() {
+        let field = smooth_field(37, 21, 9);
+        for tolerance in [1e-6, 1e-3, 0.5].iter() {
+            let model = Zfp::new(ZfpMode::FixedAccuracy(*tolerance), &[37, 21, 9]);
+            let (compressed, restored) = roundtrip_with(&model, &field);
+            bounded_with(&field, &restored, *tolerance);
+            assert!(compressed.len() * 2 < field.len() * 8);
+        }
+        let data = &normal_f32()[..65_536];
+        for (dims, tolerance) in [
+            (&[65_536][..], 1e-2),
+            (&[256, 256][..], 0.1),
+            (&[16; 4][..], 1.0),
+        ]
+        .iter()
+        {
+            let model = Zfp::new(ZfpMode::FixedAccuracy(*tolerance), dims);
+            let (_, restored) = roundtrip_with(&model, data);
+            bounded_with(data, &restored, *tolerance);
+        }
+    }
+
+    #[test]
+    fn test_fixed_rate() {
+        let field: Vec<f32> = smooth_field(64, 64, 1).iter().map(|x| *x as f32).collect();
+        let mut previous = f64::INFINITY;
+        for rate in [4.0, 8.0, 16.0].iter() {
+            let model = Zfp::new(ZfpMode::FixedRate(*rate), &[64, 64]);
+            let (compressed, restored) = roundtrip_with(&model, &field);
+            let header = ZfpHeader::read(&compressed).unwrap().size();
+            assert_eq!(compressed.len() - header, field.len() * *rate as usize / 8);
+            let error = field
+                .iter()
+                .zip(restored.iter())
+                .map(|(x, y)| (x - y).abs() as f64)
+                .fold(0.0, f64::max);
+            assert!(error < previous, "{} {}", rate, error);
+            previous = error;
+            // random access to single blocks
+            for index in [0, 17, 255].iter() {
+                let block = Zfp::<f32>::decompress_block(&compressed, *index).unwrap();
+                let (bx, by) = (4 * (index % 16), 4 * (index / 16));
+                for (j, y) in block.iter().enumerate() {
+                    assert_eq!(*y, restored[bx + j % 4 + 64 * (by + j / 4)]);
+                }
+            }
+        }
+        assert!(previous < 1e-2);
+        let model = Zfp::new(ZfpMode::FixedRate(8.0), &[7, 5]);
+        let (_, restored) = roundtrip_with(&model, &field[..35]);
+        assert_eq!(
+            Zfp::<f32>::decompress_block(&model.compress(&field[..35]).unwrap(), 3).unwrap(),
+            restored[32..]
+        );
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let data = &normal_f32()[..4_096];
+        let model = Zfp::new(ZfpMode::FixedRate(8.0), &[64, 64]);
+        let evaluation = model.evaluate(data, true).unwrap();
+        assert!((evaluation.ratio - 4.0).abs() < 0.1);
+        assert!(evaluation.errors.unwrap().ssim.is_some());
+        let model = Zfp::new(ZfpMode::FixedAccuracy(0.5), &[4_096]);
+        let errors = model.evaluate(data, true).unwrap().errors.unwrap();
+        assert!(errors.max_abs_error <= 0.5);
+        assert_eq!(errors.ssim, None);
+    }
+
+    #[test]
+    fn test_fixed_precision() {
+        let field = smooth_field(16, 8, 8);
+        let mut previous = f64::INFINITY;
+        for precision in [8, 16, 32, 64].iter() {
+            let model = Zfp::new(ZfpMode::FixedPrecision(*precision), &[16, 8, 8]);
+            let (_, restored) = roundtrip_with(&model, &field);
+            let error = field
+                .iter()
+                .zip(restored.iter())
+                .map(|(x, y)| (x - y).abs())
+                .fold(0.0, f64::max);
+            assert!(error <= previous);
+            previous = error;
+        }
+        assert!(previous < 1e-12, "{}", previous);
+    }
+
+    #[test]
+    fn test_special_values() {
+        let data = [
+            0.0f32,
+            -0.0,
+            1e-40,
+            -f32::MAX,
+            f32::MAX,
+            3.0,
+            f32::MIN_POSITIVE,
+        ];
+        let model = Zfp::new(ZfpMode::FixedPrecision(64), &[7]);
+        let (_, restored) = roundtrip_with(&model, &data);
+        assert_eq!(restored[..2], [0.0, 0.0]);
+        assert_eq!(restored[3..5], data[3..5]);
+        let model = Zfp::new(ZfpMode::FixedAccuracy(1e-3), &[4, 4, 4, 4]);
+        let (_, restored) = roundtrip_with(&model, &[0.0f64; 256]);
+        assert_eq!(restored, [0.0; 256]);
+        let model = Zfp::new(ZfpMode::FixedAccuracy(1e-3), &[4, 0, 4, 4]);
+        let (compressed, _) = roundtrip_with(&model, &[] as &[f64]);
+        assert_eq!(ZfpHeader::read(&compressed).unwrap().dims, [4, 0, 4, 4]);
+        let model = Zfp::new(ZfpMode::FixedRate(16.0), &[0, 5]);
+        roundtrip_with(&model, &[] as &[f64]);
+    }
+
+    #[test]
+    fn test_big_endian_fixture() {
+        let data = smooth_field(16, 24, 1);
+        let model = Zfp::new(ZfpMode::FixedAccuracy(1e-4), &[16, 24]);
+        // transposed data written on a big-endian host
+        let bytes: Vec<u8> = (0..16 * 24)
+            .flat_map(|i| data[(i % 24) * 16 + i / 24].to_be_bytes())
+            .collect();
+        let layout = Layout::with_endianness_and_strides(
+            DataType::F64,
+            Endianness::Big,
+            &[16, 24],
+            &[24, 1],
+        );
+        let array = Array::with_layout(layout, bytes).unwrap();
+        let compressed = model.compress_array(&array).unwrap();
+        assert_eq!(
+            ZfpHeader::read(&compressed).unwrap().endianness,
+            Endianness::Big
+        );
+        let restored = Zfp::<f64>::decompress(&compressed).unwrap();
+        bounded_with(&data, &restored, 1e-4);
+        assert_eq!(
+            restored,
+            Zfp::<f64>::decompress(&model.compress(&data).unwrap()).unwrap()
+        );
+        let restored = Zfp::<f64>::decompress_array(&compressed).unwrap();
+        assert!(restored.layout().is_contiguous());
+        assert_eq!(restored.layout().endianness, Endianness::Big);
+        let little = restored.to_endianness(Endianness::Little);
+        assert_eq!(
+            little.to_vec::<f64>().unwrap(),
+            restored.to_vec::<f64>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        let data = smooth_field(8, 8, 1);
+        let model = Zfp::new(ZfpMode::FixedAccuracy(0.1), &[8, 8]);
+        assert!(matches!(
+            model.compress(&data[1..]),
+            Err(CompressionError::ShapeMismatch(64, 63))
+        ));
+        let mut nan = data.clone();
+        nan[9] = f64::NAN;
+        assert!(matches!(
+            model.compress(&nan),
+            Err(CompressionError::NonFinite(9))
+        ));
+        for (mode, dims) in [
+            (ZfpMode::FixedRate(0.5), &[8, 8][..]),
+            (ZfpMode::FixedRate(f64::NAN), &[8, 8][..]),
+            (ZfpMode::FixedPrecision(0), &[8, 8][..]),
+            (ZfpMode::FixedPrecision(65), &[8, 8][..]),
+            (ZfpMode::FixedAccuracy(0.0), &[8, 8][..]),
+            (ZfpMode::FixedAccuracy(0.1), &[2, 2, 2, 2, 4][..]),
+            (ZfpMode::FixedAccuracy(0.1), &[][..]),
+        ]
+        .iter()
+        {
+            let model = Zfp::new(*mode, dims);
+            assert!(
+                matches!(
+                    model.compress(&data),
+                    Err(CompressionError::InvalidParameter)
+                ),
+                "{:?}",
+                mode
+            );
+        }
+        let compressed = model.compress(&data).unwrap();
+        assert!(matches!(
+            Zfp::<f32>::decompress(&compressed),
+            Err(CompressionError::TypeMismatch(8, 9))
+        ));
+        assert!(matches!(
+            Zfp::<f64>::decompress_block(&compressed, 0),
+            Err(CompressionError::InvalidParameter)
+        ));
+        let mut invalid = compressed.clone();
+        invalid[16] = 5;
+        assert!(matches!(
+            Zfp::<f64>::decompress(&invalid),
+            Err(CompressionError::InvalidHeader)
+        ));
+        invalid = compressed.clone();
+        invalid[6] = 2;
+        assert!(matches!(
+            Zfp::<f64>::decompress(&invalid),
+            Err(CompressionError::InvalidHeader)
+        ));
+        assert!(matches!(
+            Zfp::<f64>::decompress(&compressed[..ZfpHeader::read(&compressed).unwrap().size() + 2]),
+            Err(CompressionError::Coding(_))
+        ));
+        let model = Zfp::new(ZfpMode::FixedRate(8.0), &[8, 8]);
+        let compressed = model.compress(&data).unwrap();
+        assert!(matches!(
+            Zfp::<f64>::decompress(&compressed[..compressed.len() - 1]),
+            Err(CompressionError::InvalidBody)
+        ));
+    }
+}
@@ -50,6 +50,9 @@ def main():
     print(len(data))
 
 def write_to_disk(data, filename):
+    # The data is saved in little-endian order independent of the host
+    if sys.byteorder == "big":
+        data.byteswap()
     with open(filename, 'wb') as file:
         data.tofile(file)
 